@@ -1,24 +1,768 @@
 use nih_plug::buffer::Buffer;
+use nih_plug::nih_warn;
+use std::collections::VecDeque;
 use rustfft::FftPlanner;
+use rustfft::num_complex::Complex;
+use crate::builder::AnalyzerBuilder;
+use crate::config::{AnalyzerConfig, ConfigError, MAX_FFT_SIZE};
+use crate::cqt::{CqtKernel, CqtResult};
+use crate::fft::{mirror_spectrum, DefaultFftBackend, FftBackend, ForwardFft};
+use crate::mel::{MelFilterbank, MelResult};
+use crate::settings::{AnalyzerSettings, SettingsGuard};
+use crate::sweep::{ExponentialSweep, ImpulseResponse, SweepCapture};
+use crate::transfer::{TransferAverage, TransferResult};
+use crate::weighting::WeightingCurve;
+use crate::window::WindowFunction;
+
+/// The standard tuning of A4 in Hz, for use with [`Analyzer::chromagram()`].
+pub const DEFAULT_REFERENCE_A4: f32 = 440.0;
+
+/// The fraction of the energy that is usually used for [`Analyzer::spectral_rolloff()`].
+pub const DEFAULT_ROLLOFF_PERCENTAGE: f32 = 0.85;
+
+/// The largest fraction of a frame consecutive frames of [`Analyzer::poll()`] can share.
+pub const MAX_OVERLAP: f32 = 0.99;
+
+/// The lowest level in dB [`Analyzer::process_db()`] reports by default.
+pub const DEFAULT_FLOOR_DB: f32 = -120.0;
+
+/// The frequency in Hz the spectral tilt is anchored at by default. Bins at this frequency are
+/// left as is.
+const DEFAULT_SLOPE_REFERENCE: f32 = 1000.0;
+
+/// How far in dB the smoothed magnitudes must have decayed after the input goes silent before
+/// the display is considered settled, see [`Analyzer::smoothing_decay_frames()`].
+const DECAY_RANGE_DB: f32 = 60.0;
+
+/// The factor the FFT size is multiplied by in the offline profile.
+const OFFLINE_FFT_SIZE_FACTOR: usize = 4;
+
+/// The least overlap of the frames of [`Analyzer::poll()`] in the offline profile.
+const OFFLINE_MIN_OVERLAP: f32 = 0.75;
+
+/// The ratio between the FFT sizes of the two analyses in smart resolution mode.
+const SMART_RESOLUTION_RATIO: usize = 4;
+
+/// The factor the signal is decimated by for the low frequency analysis in multiresolution mode.
+const MULTIRESOLUTION_DECIMATION: usize = 4;
+
+/// The number of taps of the low-pass filter that is applied before decimating.
+const DECIMATION_FILTER_TAPS: usize = 63;
 
 /// Implements a Spectrum Analyzer.
 pub struct Analyzer {
+    /// Plans the FFTs of the spectrum analysis.
+    fft_backend: DefaultFftBackend,
+    /// Plans the complex FFTs of the sweep and CQT analyses.
     fft_planner: FftPlanner<f32>,
     sample_rate: f32,
+    /// The configuration for all channels that don't have an override, as it was configured.
+    config: AnalyzerConfig,
+    /// Configurations that replace [`Self::config`] for individual channels.
+    channel_overrides: Vec<Option<AnalyzerConfig>>,
+    /// The analysis state for every channel that has been processed.
+    channels: Vec<ChannelState>,
+    /// The center frequency of every bin for the global configuration.
+    frequencies: Vec<f32>,
+    /// The magnitudes of the last processed block, averaged over all channels.
+    average: Vec<f32>,
+    /// The distance in Hz between two bins of [`Self::average`].
+    average_bin_width: f32,
+    /// The frequency range in Hz over which [`Analyzer::slope_deviation()`] fits its line.
+    slope_fit_range: (f32, f32),
+    /// The frequency range in Hz the results are limited to, if any.
+    frequency_range: Option<(f32, f32)>,
+    /// How the channels of a buffer are turned into analyzed signals.
+    channel_mode: ChannelMode,
+    /// The signals derived from the channels of a buffer for channel modes other than
+    /// [`ChannelMode::Discrete`]. These are kept around so they don't need to be reallocated.
+    mixed: Vec<Vec<f32>>,
+    /// The samples passed to [`Analyzer::push_samples()`] that have not been analyzed yet, for
+    /// every channel.
+    pending: Vec<VecDeque<f32>>,
+    /// The most samples a queue of [`Self::pending`] holds, or `None` if the queues grow
+    /// indefinitely.
+    pending_capacity: Option<usize>,
+    /// The number of samples every queue of [`Self::pending`] discarded because it was full,
+    /// since the last reset.
+    discarded: Vec<u64>,
+    /// The fraction of a frame that consecutive frames of [`Analyzer::poll()`] share.
+    overlap: f32,
+    /// The cutoff frequency in Hz of the high-pass pre-filter, or `0.0` if it's disabled.
+    prefilter_cutoff: f32,
+    /// Whether [`AnalyzerResult::phases`] should be filled in.
+    phase_output: bool,
+    /// The width in octaves of the window the unwrapped phase is smoothed over, or `0.0` to
+    /// leave the phase unsmoothed.
+    phase_smoothing: f32,
+    /// How much of the previous frame's magnitudes is kept in every new frame, or `0.0` to leave
+    /// the magnitudes unsmoothed.
+    smoothing: f32,
+    /// The time constant in seconds [`Self::smoothing`] is derived from, if the smoothing was set
+    /// as a time.
+    smoothing_time: Option<f32>,
+    /// The number of samples between the starts of two frames the smoothing coefficient was
+    /// derived for, if it was derived from [`Self::smoothing_time`].
+    smoothing_interval: usize,
+    /// The tilt in dB per octave that is applied to the magnitudes.
+    slope_db_per_octave: f32,
+    /// The frequency in Hz the tilt is anchored at.
+    slope_reference: f32,
+    /// Whether the magnitudes are amplitudes or powers.
+    magnitude_scale: MagnitudeScale,
+    /// The lowest level in dB of the magnitudes of [`Analyzer::process_db()`].
+    floor_db: f32,
+    /// The channel [`Analyzer::relative_group_delay()`] measures against, if any.
+    reference_channel: Option<usize>,
+    /// Whether the results include the Nyquist bin at `fft_size / 2`.
+    include_nyquist: bool,
+    /// The crossover frequency in Hz of the smart resolution mode, or `None` if it's disabled.
+    smart_crossover: Option<f32>,
+    /// Whether the low frequencies are analyzed from a decimated signal.
+    multiresolution: bool,
+    /// Whether the magnitudes are normalized by the frame length, see
+    /// [`Analyzer::set_amplitude_correction()`].
+    amplitude_correction: bool,
+    /// The anti-aliasing filter that is applied before decimating in multiresolution mode.
+    decimation_filter: Vec<f32>,
+    /// The results of the last processed block, which are reused for the next one.
+    results: Vec<AnalyzerResult>,
+    /// Whether a buffer with channels of different lengths has been reported, so it's only
+    /// logged once.
+    reported_length_mismatch: bool,
+    /// Whether the results are held instead of being updated, see [`Analyzer::set_frozen()`].
+    frozen: bool,
+    /// The level in dB below which a block is silent, see [`Analyzer::set_silence_threshold()`].
+    silence_threshold_db: f32,
+    /// The number of consecutive silent blocks that weren't analyzed.
+    silent_blocks: usize,
+    /// The number of frames results were produced for since the last reset.
+    frames_processed: u64,
+    /// The number of frames the full queues of [`Self::pending`] lost since the last reset.
+    frames_dropped: u64,
+    /// Whether the offline profile is active, see [`Analyzer::set_offline()`].
+    offline: bool,
+    /// The configuration that is analyzed for all channels that don't have an override. This is
+    /// [`Self::config`] with the FFT size of the current profile.
+    profile_config: AnalyzerConfig,
+    /// The largest FFT size [`Analyzer::prepare_fft_sizes()`] made room for.
+    max_prepared_fft_size: usize,
+    /// The fixed result [`Analyzer::process()`] returns instead of analyzing the buffer, see
+    /// [`Analyzer::set_synthetic()`].
+    #[cfg(debug_assertions)]
+    synthetic: Option<AnalyzerResult>,
+    /// The impulse response measurement that is in progress, if any.
+    sweep_capture: Option<SweepCapture>,
+    /// The spectra the transfer function is averaged over, if it's being measured.
+    transfer: Option<TransferAverage>,
+    /// The kernel of the last constant-Q transform, which is reused while its parameters stay
+    /// the same.
+    cqt_kernel: Option<CqtKernel>,
+    /// The filterbank of the last mel analysis, which is reused while its parameters and the
+    /// bins it applies to stay the same.
+    mel_filterbank: Option<MelFilterbank>,
 }
 
+#[derive(Debug, Default, PartialEq)]
 pub struct AnalyzerResult {
     pub frequencies: Vec<f32>,
     pub magnitudes: Vec<f32>,
+    /// The name of the analyzed signal, if it is not simply the channel at the result's index.
+    pub label: Option<String>,
+    /// The index of the channel the result was analyzed from. Results are always in channel
+    /// order, so this is also the result's index. The signals of [`ChannelMode::MidSide`] have
+    /// the index of their position, mid first, and the signal of [`ChannelMode::Sum`] has index
+    /// `0`.
+    pub channel: usize,
+    /// The phase of every bin in radians, in the range (-π, π]. This is only computed when phase
+    /// output is enabled with [`Analyzer::set_phase_output()`]. When phase smoothing is enabled
+    /// with [`Analyzer::set_phase_smoothing()`] the phase is unwrapped instead, and is no longer
+    /// limited to that range.
+    pub phases: Option<Vec<f32>>,
+    /// The index of the first bin of the result in the full analysis. This is `0` unless the
+    /// result is limited to a range with [`Analyzer::set_frequency_range()`].
+    pub start_bin: usize,
+    /// The RMS level of the analyzed frame, before windowing. Blocks that are skipped as silent
+    /// report a level of zero.
+    pub rms: f32,
+    /// The largest absolute sample of the analyzed frame, before windowing.
+    pub peak: f32,
+}
+
+/// A power spectral density estimate, as computed by [`Analyzer::process_psd()`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PsdResult {
+    /// The center frequency in Hz of every bin.
+    pub frequencies: Vec<f32>,
+    /// The one-sided power spectral density of every bin in magnitude² per Hz.
+    pub power: Vec<f32>,
+}
+
+/// Determines how the channels of a buffer are turned into the signals that get analyzed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelMode {
+    /// Every channel is analyzed on its own.
+    #[default]
+    Discrete,
+    /// A stereo pair is analyzed as its mid (`(L + R) / 2`) and side (`(L - R) / 2`) signals,
+    /// labeled "Mid" and "Side". Buffers that don't have exactly two channels are analyzed as
+    /// [`ChannelMode::Discrete`].
+    MidSide,
+    /// All channels are averaged sample by sample into a single signal, labeled "Sum". Dividing
+    /// by the channel count keeps the sum of correlated channels from exceeding full scale.
+    Sum,
+}
+
+/// Determines what the magnitudes of an [`AnalyzerResult`] measure.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MagnitudeScale {
+    /// The amplitude of every bin, in the units of the samples. A full scale sine gives a
+    /// magnitude of `1.0` with amplitude correction enabled.
+    #[default]
+    Linear,
+    /// The power of every bin, the square of its amplitude, which is what energy-based measures
+    /// such as a PSD or a spectral centroid are built on. The helpers that read magnitudes as
+    /// amplitudes, such as [`AnalyzerResult::points_db()`] and [`Analyzer::spectral_rolloff()`],
+    /// expect [`MagnitudeScale::Linear`].
+    Power,
+}
+
+impl Clone for AnalyzerResult {
+    fn clone(&self) -> Self {
+        AnalyzerResult {
+            frequencies: self.frequencies.clone(),
+            magnitudes: self.magnitudes.clone(),
+            label: self.label.clone(),
+            channel: self.channel,
+            phases: self.phases.clone(),
+            start_bin: self.start_bin,
+            rms: self.rms,
+            peak: self.peak,
+        }
+    }
+
+    /// Copy another result into this one, reusing this result's buffers where they are large
+    /// enough. This is what makes handing results to another thread allocation free.
+    fn clone_from(&mut self, source: &Self) {
+        self.frequencies.clone_from(&source.frequencies);
+        self.magnitudes.clone_from(&source.magnitudes);
+        self.label.clone_from(&source.label);
+        self.channel = source.channel;
+        self.phases.clone_from(&source.phases);
+        self.start_bin = source.start_bin;
+        self.rms = source.rms;
+        self.peak = source.peak;
+    }
+}
+
+impl AnalyzerResult {
+    /// Get every bin as a `[frequency, magnitude]` point, for plotting libraries that take a
+    /// list of points instead of two parallel vectors.
+    pub fn points(&self) -> Vec<[f32; 2]> {
+        self.frequencies
+            .iter()
+            .zip(&self.magnitudes)
+            .map(|(&frequency, &magnitude)| [frequency, magnitude])
+            .collect()
+    }
+
+    /// Get every bin as a `[frequency, magnitude]` point with the magnitude in dB. No level is
+    /// below `floor_db`, so bins without any energy are at the floor instead of negative
+    /// infinity. Pass [`Analyzer::floor_db()`] to get the levels of [`Analyzer::process_db()`].
+    pub fn points_db(&self, floor_db: f32) -> Vec<[f32; 2]> {
+        self.frequencies
+            .iter()
+            .zip(&self.magnitudes)
+            .map(|(&frequency, &magnitude)| [frequency, amplitude_to_db(magnitude, floor_db)])
+            .collect()
+    }
+
+    /// Get the magnitude at an arbitrary frequency in Hz, interpolated linearly between the two
+    /// bins around it. Frequencies below the first bin or above the last bin get that bin's
+    /// magnitude, as nothing is extrapolated. Returns `0.0` if the result has no bins.
+    pub fn magnitude_at(&self, frequency: f32) -> f32 {
+        let above = self.frequencies.partition_point(|&bin| bin < frequency);
+        if above == 0 {
+            return self.magnitudes.first().copied().unwrap_or(0.0);
+        }
+        if above == self.frequencies.len() {
+            return self.magnitudes[above - 1];
+        }
+
+        let below = above - 1;
+        let (low, high) = (self.frequencies[below], self.frequencies[above]);
+        let fraction = (frequency - low) / (high - low);
+        self.magnitudes[below] + fraction * (self.magnitudes[above] - self.magnitudes[below])
+    }
+
+    /// Get the difference between the magnitudes of this result and `other` for every bin, as
+    /// `self - other` in whatever scale both are in. This compares a live spectrum against a
+    /// captured one, for example. Returns nothing if the results don't have the same number of
+    /// bins.
+    pub fn diff(&self, other: &AnalyzerResult) -> Vec<f32> {
+        if self.magnitudes.len() != other.magnitudes.len() {
+            return Vec::new();
+        }
+
+        self.magnitudes.iter().zip(&other.magnitudes).map(|(a, b)| a - b).collect()
+    }
+}
+
+/// The analysis state of a single channel. The tables and FFT plans are derived from the
+/// channel's configuration, and are only recomputed when that configuration or the sample rate
+/// changes.
+#[derive(Default)]
+struct ChannelState {
+    /// The configuration and sample rate the tables were computed for.
+    prepared_for: Option<(AnalyzerConfig, f32)>,
+    /// The window coefficients for every sample in a frame.
+    window: Vec<f32>,
+    /// The coherent gain of the window, which the amplitude correction compensates for.
+    coherent_gain: f32,
+    /// The linear weighting gain for every bin.
+    weighting_gains: Vec<f32>,
+    /// The window coefficients for the smaller frame used in smart resolution mode.
+    small_window: Vec<f32>,
+    /// Every FFT plan made for this channel. Plans are kept when the FFT size changes, so
+    /// switching back to an earlier size, or to one of the sizes planned ahead by
+    /// [`Analyzer::prepare_fft_sizes()`], doesn't plan again.
+    plans: Vec<Box<dyn ForwardFft>>,
+    /// The index in [`Self::plans`] of the plan for the channel's FFT size.
+    fft: Option<usize>,
+    /// The index in [`Self::plans`] of the plan for the smaller FFT used in smart resolution
+    /// mode.
+    small_fft: Option<usize>,
+    /// The buffer a frame is copied into to transform it. Every channel has its own, so channels
+    /// can be analyzed in parallel.
+    scratch: Vec<Complex<f32>>,
+    /// The linear weighting gain for every bin of the decimated analysis in multiresolution
+    /// mode.
+    low_weighting_gains: Vec<f32>,
+    /// The slope, reference frequency, FFT size and sample rate the slope gains were computed
+    /// for.
+    slope_prepared_for: Option<(f32, f32, usize, f32)>,
+    /// The linear gain of the spectral tilt for every bin.
+    slope_gains: Vec<f32>,
+    /// The linear gain of the spectral tilt for every bin of the decimated analysis in
+    /// multiresolution mode.
+    low_slope_gains: Vec<f32>,
+    /// The magnitudes of the decimated analysis in multiresolution mode.
+    low_magnitudes: Vec<f32>,
+    /// The smoothed magnitudes of the previous frame.
+    smoothed: Vec<f32>,
+    /// The last input and output sample of the high-pass pre-filter, which carry over from one
+    /// block to the next.
+    prefilter_state: (f32, f32),
+    /// The samples of the current block after the high-pass pre-filter.
+    filtered: Vec<f32>,
+}
+
+/// The settings shared by all channels that [`ChannelState::analyze()`] needs from the
+/// [`Analyzer`].
+struct FrameSettings<'a> {
+    sample_rate: f32,
+    include_nyquist: bool,
+    phase_output: bool,
+    phase_smoothing: f32,
+    smoothing: f32,
+    slope_db_per_octave: f32,
+    slope_reference: f32,
+    magnitude_scale: MagnitudeScale,
+    smart_crossover: Option<f32>,
+    /// The coefficient of the high-pass pre-filter, if it's enabled.
+    prefilter: Option<f32>,
+    /// The anti-aliasing filter for the decimated analysis, if multiresolution mode is enabled.
+    decimation_filter: Option<&'a [f32]>,
+    amplitude_correction: bool,
+    /// The FFT size of the global configuration, which [`Self::frequencies`] belongs to.
+    global_fft_size: usize,
+    frequencies: &'a [f32],
+}
+
+impl ChannelState {
+    /// Recompute the tables if they don't match the configuration and sample rate, and select the
+    /// FFT plans for the FFT size, planning them if that size wasn't planned before.
+    fn prepare(
+        &mut self,
+        config: &AnalyzerConfig,
+        sample_rate: f32,
+        fft_backend: &mut DefaultFftBackend,
+    ) {
+        if self.prepared_for == Some((*config, sample_rate)) {
+            return;
+        }
+
+        let small_size = config.fft_size / SMART_RESOLUTION_RATIO;
+        self.fft = Some(self.plan(config.fft_size, fft_backend));
+        self.small_fft = (small_size >= 2).then(|| self.plan(small_size, fft_backend));
+
+        config.window.fill(&mut self.window, config.fft_size);
+        self.coherent_gain = config.window.coherent_gain(config.fft_size);
+        self.scratch.resize(config.fft_size, Complex::new(0.0, 0.0));
+        reserve_total(&mut self.smoothed, result_capacity(config.fft_size));
+        config
+            .window
+            .fill(&mut self.small_window, config.fft_size / SMART_RESOLUTION_RATIO);
+
+        let bin_width = sample_rate / config.fft_size as f32;
+        self.weighting_gains.clear();
+        self.weighting_gains.extend(
+            (0..=config.fft_size / 2).map(|i| config.weighting.gain(i as f32 * bin_width)),
+        );
+        let low_bin_width = bin_width / MULTIRESOLUTION_DECIMATION as f32;
+        self.low_weighting_gains.clear();
+        self.low_weighting_gains.extend(
+            (0..config.fft_size / MULTIRESOLUTION_DECIMATION)
+                .map(|i| config.weighting.gain(i as f32 * low_bin_width)),
+        );
+
+        self.prepared_for = Some((*config, sample_rate));
+    }
+
+    /// Plan the FFTs for frames of `fft_size` samples and make room in the tables for that size,
+    /// so switching to it later neither plans nor allocates.
+    fn prepare_fft_size(&mut self, fft_size: usize, fft_backend: &mut DefaultFftBackend) {
+        let small_size = fft_size / SMART_RESOLUTION_RATIO;
+        self.plan(fft_size, fft_backend);
+        if small_size >= 2 {
+            self.plan(small_size, fft_backend);
+        }
+
+        reserve_total(&mut self.window, fft_size);
+        reserve_total(&mut self.small_window, small_size);
+        reserve_total(&mut self.scratch, fft_size);
+        reserve_total(&mut self.weighting_gains, fft_size / 2 + 1);
+        reserve_total(&mut self.low_weighting_gains, fft_size / MULTIRESOLUTION_DECIMATION);
+        reserve_total(&mut self.slope_gains, fft_size / 2 + 1);
+        reserve_total(&mut self.low_slope_gains, fft_size / MULTIRESOLUTION_DECIMATION);
+        reserve_total(&mut self.low_magnitudes, fft_size / MULTIRESOLUTION_DECIMATION);
+        reserve_total(&mut self.smoothed, result_capacity(fft_size));
+    }
+
+    /// Recompute the gains of the spectral tilt if they don't match the slope, the reference
+    /// frequency, the FFT size and the sample rate. The tilt is only changed now and then, so
+    /// this keeps `powf()` and `log2()` out of every frame.
+    fn prepare_slope(&mut self, slope: f32, reference: f32, sample_rate: f32) {
+        let fft_size = self.scratch.len();
+        if self.slope_prepared_for == Some((slope, reference, fft_size, sample_rate)) {
+            return;
+        }
+
+        let bin_width = sample_rate / fft_size as f32;
+        self.slope_gains.clear();
+        self.slope_gains.extend(
+            (0..=fft_size / 2).map(|i| slope_gain(i as f32 * bin_width, slope, reference)),
+        );
+        let low_bin_width = bin_width / MULTIRESOLUTION_DECIMATION as f32;
+        self.low_slope_gains.clear();
+        self.low_slope_gains.extend(
+            (0..fft_size / MULTIRESOLUTION_DECIMATION)
+                .map(|i| slope_gain(i as f32 * low_bin_width, slope, reference)),
+        );
+
+        self.slope_prepared_for = Some((slope, reference, fft_size, sample_rate));
+    }
+
+    /// Get the index in [`Self::plans`] of the plan for an FFT of `len` samples, and plan it if
+    /// there is none yet.
+    fn plan(&mut self, len: usize, fft_backend: &mut DefaultFftBackend) -> usize {
+        match self.plans.iter().position(|plan| plan.len() == len) {
+            Some(index) => index,
+            None => {
+                self.plans.push(fft_backend.plan(len));
+                self.plans.len() - 1
+            }
+        }
+    }
+
+    /// Window the most recent frame of `samples` and transform it into [`Self::scratch`], which
+    /// then holds the complex spectrum up to and including the Nyquist bin. Returns the RMS level
+    /// and the peak of the frame, which are measured while it's copied.
+    fn transform(&mut self, samples: &[f32]) -> (f32, f32) {
+        let fft_size = self.scratch.len();
+
+        // We don't want to change the original samples, so we copy the most recent `fft_size`
+        // samples into the scratch buffer, because we need to convert the samples to complex
+        // numbers and [`fft.process()`] will modify the samples in place. Blocks that are shorter
+        // than a frame are padded with zeros.
+        let frame = &samples[samples.len().saturating_sub(fft_size)..];
+        let (mut sum_of_squares, mut peak) = (0.0, 0.0_f32);
+        for (i, bin) in self.scratch.iter_mut().enumerate() {
+            let sample = frame.get(i).copied().unwrap_or(0.0);
+            sum_of_squares += sample * sample;
+            peak = peak.max(sample.abs());
+            *bin = Complex::new(sample * self.window[i], 0.0);
+        }
+
+        if let Some(fft) = self.fft {
+            self.plans[fft].process(&mut self.scratch);
+        }
+
+        // The zeros a short block is padded with aren't part of the signal, so they don't lower
+        // its RMS level.
+        let rms = if frame.is_empty() { 0.0 } else { (sum_of_squares / frame.len() as f32).sqrt() };
+        (rms, peak)
+    }
+
+    /// Analyze a frame of samples with the prepared tables, and write the result into `result`.
+    fn analyze(&mut self, samples: &[f32], settings: &FrameSettings, result: &mut AnalyzerResult) {
+        let mut filtered = std::mem::take(&mut self.filtered);
+        let samples = match settings.prefilter {
+            Some(coefficient) => {
+                self.high_pass(samples, coefficient, &mut filtered);
+                &filtered[..]
+            }
+            None => samples,
+        };
+        let fft_size = self.scratch.len();
+        let bin_count = if settings.include_nyquist { fft_size / 2 + 1 } else { fft_size / 2 };
+
+        (result.rms, result.peak) = self.transform(samples);
+        let complex_samples = &self.scratch[..fft_size];
+
+        if settings.phase_output {
+            let phases = result.phases.get_or_insert_with(Vec::new);
+            phases.clear();
+            phases.extend(complex_samples[..bin_count].iter().map(|bin| {
+                // `atan2()` returns -π for bins on the negative real axis with a negative zero
+                // imaginary part, which is the same angle as π.
+                let phase = bin.im.atan2(bin.re);
+                if phase == -std::f32::consts::PI { std::f32::consts::PI } else { phase }
+            }));
+
+            if settings.phase_smoothing > 0.0 {
+                unwrap_phase(phases);
+                smooth_fractional_octave(phases, settings.phase_smoothing);
+            }
+        } else {
+            result.phases = None;
+        }
+
+        let magnitudes = &mut result.magnitudes;
+        magnitudes.clear();
+        for bin in &complex_samples[..bin_count] {
+            let magnitude = (bin.re.powi(2) + bin.im.powi(2)).sqrt();
+            magnitudes.push(magnitude);
+        }
+
+        if let (Some(crossover), Some(small_fft)) = (settings.smart_crossover, self.small_fft) {
+            let small_fft = &mut self.plans[small_fft];
+            let small_size = small_fft.len();
+            let small_frame = &samples[samples.len().saturating_sub(small_size)..];
+            let small_samples = &mut self.scratch[..small_size];
+            for (i, bin) in small_samples.iter_mut().enumerate() {
+                let sample = small_frame.get(i).copied().unwrap_or(0.0);
+                *bin = Complex::new(sample * self.small_window[i], 0.0);
+            }
+
+            small_fft.process(small_samples);
+            blend_smart_resolution(
+                magnitudes,
+                &small_samples[..=small_size / 2],
+                settings.sample_rate / fft_size as f32,
+                crossover,
+            );
+        }
+
+        // The weighting is applied after the magnitude computation, so it scales the magnitude of
+        // each bin by the gain of the curve at the bin's center frequency.
+        if self.prepared_for.is_some_and(|(config, _)| config.weighting != WeightingCurve::None) {
+            for (magnitude, gain) in magnitudes.iter_mut().zip(&self.weighting_gains) {
+                *magnitude *= gain;
+            }
+        }
+
+        if settings.amplitude_correction {
+            correct_amplitudes(magnitudes, fft_size, self.coherent_gain);
+        }
+
+        let tilted = settings.slope_db_per_octave != 0.0;
+        if tilted {
+            let (slope, reference) = (settings.slope_db_per_octave, settings.slope_reference);
+            self.prepare_slope(slope, reference, settings.sample_rate);
+            for (magnitude, gain) in magnitudes.iter_mut().zip(&self.slope_gains) {
+                *magnitude *= gain;
+            }
+        }
+
+        // The decimated analysis covers the bottom quarter of its band with four times the
+        // resolution, and replaces the full rate bins below the top of that range.
+        let low_bins = fft_size / MULTIRESOLUTION_DECIMATION;
+        let high_start = low_bins / MULTIRESOLUTION_DECIMATION;
+        if let (Some(filter), true) = (settings.decimation_filter, high_start > 0) {
+            self.analyze_decimated(samples, filter, low_bins);
+            if settings.amplitude_correction {
+                correct_amplitudes(&mut self.low_magnitudes, fft_size, self.coherent_gain);
+            }
+            if tilted {
+                for (magnitude, gain) in self.low_magnitudes.iter_mut().zip(&self.low_slope_gains) {
+                    *magnitude *= gain;
+                }
+            }
+            magnitudes.splice(..high_start, self.low_magnitudes.iter().copied());
+            result.phases = None;
+
+            let high_bin_width = settings.sample_rate / fft_size as f32;
+            let low_bin_width = high_bin_width / MULTIRESOLUTION_DECIMATION as f32;
+            result.frequencies.clear();
+            result.frequencies.extend((0..low_bins).map(|i| i as f32 * low_bin_width));
+            result.frequencies.extend((high_start..bin_count).map(|i| i as f32 * high_bin_width));
+        } else {
+            // Channels using the global FFT size share the cached frequency axis, only overrides
+            // with a different FFT size need their own.
+            result.frequencies.clear();
+            if fft_size == settings.global_fft_size {
+                result.frequencies.extend_from_slice(settings.frequencies);
+            } else {
+                let bin_width = settings.sample_rate / fft_size as f32;
+                result.frequencies.extend((0..bin_count).map(|i| i as f32 * bin_width));
+            }
+        }
+
+        // The corrections above all scale amplitudes, so the magnitudes only become powers once
+        // they're done. Smoothing then averages the powers.
+        if settings.magnitude_scale == MagnitudeScale::Power {
+            for magnitude in &mut result.magnitudes {
+                *magnitude *= *magnitude;
+            }
+        }
+        self.smooth(&mut result.magnitudes, settings.smoothing);
+        self.filtered = filtered;
+    }
+
+    /// Filter `samples` into `filtered` with a one-pole high-pass filter, continuing from where
+    /// the previous block left off.
+    fn high_pass(&mut self, samples: &[f32], coefficient: f32, filtered: &mut Vec<f32>) {
+        let (mut previous_input, mut previous_output) = self.prefilter_state;
+        filtered.clear();
+        filtered.extend(samples.iter().map(|&sample| {
+            previous_output = coefficient * (previous_output + sample - previous_input);
+            previous_input = sample;
+            previous_output
+        }));
+        self.prefilter_state = (previous_input, previous_output);
+    }
+
+    /// Decay the magnitudes of the previous frame as if a silent frame was smoothed into them.
+    /// The smoothed magnitudes that are remembered for the next frame decay the same way, which
+    /// keeps them in sync when the result only holds some of their bins.
+    fn decay(&mut self, magnitudes: &mut [f32], smoothing: f32) {
+        for magnitude in magnitudes.iter_mut().chain(&mut self.smoothed) {
+            *magnitude *= smoothing;
+        }
+    }
+
+    /// Blend the magnitudes with the smoothed magnitudes of the previous frame, and remember the
+    /// result for the next frame. Nothing is blended when the number of bins changed since the
+    /// previous frame.
+    fn smooth(&mut self, magnitudes: &mut [f32], smoothing: f32) {
+        if smoothing > 0.0 && self.smoothed.len() == magnitudes.len() {
+            for (magnitude, previous) in magnitudes.iter_mut().zip(&self.smoothed) {
+                *magnitude = smoothing * previous + (1.0 - smoothing) * *magnitude;
+            }
+        }
+
+        self.smoothed.clear();
+        self.smoothed.extend_from_slice(magnitudes);
+    }
+
+    /// Low-pass filter and decimate the most recent `fft_size * MULTIRESOLUTION_DECIMATION`
+    /// samples, transform them, and store the magnitudes of the first `low_bins` bins in
+    /// [`Self::low_magnitudes`].
+    fn analyze_decimated(&mut self, samples: &[f32], filter: &[f32], low_bins: usize) {
+        let fft_size = self.scratch.len();
+        let start = samples.len().saturating_sub(fft_size * MULTIRESOLUTION_DECIMATION);
+        let half = filter.len() / 2;
+        for (i, bin) in self.scratch.iter_mut().enumerate() {
+            let center = start + i * MULTIRESOLUTION_DECIMATION;
+            let sample = filter
+                .iter()
+                .enumerate()
+                .filter_map(|(tap, coefficient)| {
+                    let index = (center + tap).checked_sub(half)?;
+                    samples.get(index).map(|sample| sample * coefficient)
+                })
+                .sum::<f32>();
+            *bin = Complex::new(sample * self.window[i], 0.0);
+        }
+
+        if let Some(fft) = self.fft {
+            self.plans[fft].process(&mut self.scratch);
+        }
+
+        self.low_magnitudes.clear();
+        self.low_magnitudes.extend(self.scratch[..low_bins].iter().map(|bin| bin.norm()));
+        if self.prepared_for.is_some_and(|(config, _)| config.weighting != WeightingCurve::None) {
+            for (magnitude, gain) in self.low_magnitudes.iter_mut().zip(&self.low_weighting_gains) {
+                *magnitude *= gain;
+            }
+        }
+    }
 }
 
 impl Analyzer {
+    /// Start configuring an [`Analyzer`] with an [`AnalyzerBuilder`], which checks the whole
+    /// configuration at once.
+    pub fn builder() -> AnalyzerBuilder {
+        AnalyzerBuilder::default()
+    }
+
     /// Create a new instance of [`Analyzer`] with defaults.
     pub fn new(sample_rate: f32) -> Self {
-        Analyzer {
+        let mut analyzer = Analyzer {
+            fft_backend: DefaultFftBackend::new(),
             fft_planner: FftPlanner::new(),
             sample_rate,
-        }
+            config: AnalyzerConfig::default(),
+            profile_config: AnalyzerConfig::default(),
+            channel_overrides: Vec::new(),
+            channels: Vec::new(),
+            frequencies: Vec::new(),
+            average: Vec::new(),
+            average_bin_width: 0.0,
+            slope_fit_range: (100.0, 10_000.0),
+            frequency_range: None,
+            channel_mode: ChannelMode::Discrete,
+            phase_output: false,
+            phase_smoothing: 0.0,
+            smoothing: 0.0,
+            smoothing_time: None,
+            smoothing_interval: 0,
+            slope_db_per_octave: 0.0,
+            slope_reference: DEFAULT_SLOPE_REFERENCE,
+            magnitude_scale: MagnitudeScale::Linear,
+            floor_db: DEFAULT_FLOOR_DB,
+            reference_channel: None,
+            include_nyquist: false,
+            smart_crossover: None,
+            multiresolution: false,
+            amplitude_correction: true,
+            decimation_filter: decimation_filter(),
+            results: Vec::new(),
+            reported_length_mismatch: false,
+            frozen: false,
+            silence_threshold_db: f32::NEG_INFINITY,
+            silent_blocks: 0,
+            frames_processed: 0,
+            frames_dropped: 0,
+            offline: false,
+            max_prepared_fft_size: 0,
+            #[cfg(debug_assertions)]
+            synthetic: None,
+            sweep_capture: None,
+            transfer: None,
+            cqt_kernel: None,
+            mel_filterbank: None,
+            mixed: Vec::new(),
+            pending: Vec::new(),
+            pending_capacity: None,
+            discarded: Vec::new(),
+            overlap: 0.0,
+            prefilter_cutoff: 0.0,
+        };
+        analyzer.update_frequencies();
+
+        analyzer
     }
 
     /// Get the sample rate for the analyzer to use.
@@ -26,42 +770,1937 @@ impl Analyzer {
         self.sample_rate
     }
 
-    /// Set the sample rate for the analyzer to use.
+    /// Set the sample rate for the analyzer to use. The frequency axis is updated in place, and
+    /// the per-channel tables are recomputed on the next block, so this doesn't reallocate.
+    /// Samples queued with [`Analyzer::push_samples()`] were captured at the old sample rate, so
+    /// they are discarded.
     pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        if sample_rate == self.sample_rate {
+            return;
+        }
+
         self.sample_rate = sample_rate;
+        self.update_frequencies();
+        self.update_smoothing();
+        for queue in &mut self.pending {
+            queue.clear();
+        }
     }
 
-    /// Process the buffer and analyze the spectrum.
-    pub fn process(&mut self, buffer: &mut Buffer) -> Vec<AnalyzerResult> {
-        let sample_count = buffer.samples();
-        let fft = self.fft_planner.plan_fft_forward(sample_count);
-        let mut results = Vec::new();
+    /// Get the configuration that applies to all channels without an override. Its FFT size is
+    /// the configured one, also in the offline profile, so it can be applied again as is. Use
+    /// [`Analyzer::fft_size()`] for the size that is analyzed.
+    pub fn config(&self) -> &AnalyzerConfig {
+        &self.config
+    }
 
-        for channel_samples in buffer.as_slice() {
-            // We don't want to change the original samples, so we make a copy of them, because we
-            // need to convert the samples  to complex numbers and [`fft.process()`] will modify
-            // the samples in place.
-            let mut complex_samples = channel_samples.into_iter()
-                .map(|&mut sample| rustfft::num_complex::Complex::new(sample, 0.0))
-                .collect::<Vec<_>>();
+    /// Replace the configuration that applies to all channels without an override. The
+    /// configuration is validated against the current sample rate first, and is not applied if
+    /// it's invalid.
+    pub fn apply_config(&mut self, config: AnalyzerConfig) -> Result<(), ConfigError> {
+        config.validate(self.sample_rate)?;
+        self.config = config;
+        let fft_size = self.profile_fft_size(config.fft_size);
+        self.profile_config = AnalyzerConfig { fft_size, ..config };
+        self.update_frequencies();
+        self.update_smoothing();
 
-            fft.process(&mut complex_samples[..]);
-            let fft_size = complex_samples.len();
+        Ok(())
+    }
 
-            let mut magnitudes = Vec::with_capacity(fft_size / 2);
-            for i in 0..fft_size / 2 {
-                let bin = complex_samples[i];
-                let magnitude = (bin.re.powi(2) + bin.im.powi(2)).sqrt();
-                magnitudes.push(magnitude);
-            }
+    /// Get a snapshot of every setting, to restore later with [`Analyzer::apply_settings()`].
+    pub fn settings(&self) -> AnalyzerSettings {
+        AnalyzerSettings {
+            config: self.config,
+            offline: self.offline,
+            channel_overrides: self.channel_overrides.clone(),
+            channel_mode: self.channel_mode,
+            frequency_range: self.frequency_range,
+            overlap: self.overlap,
+            pending_capacity: self.pending_capacity,
+            prefilter_cutoff: self.prefilter_cutoff,
+            phase_output: self.phase_output,
+            phase_smoothing: self.phase_smoothing,
+            smoothing: self.smoothing,
+            smoothing_time: self.smoothing_time,
+            slope_db_per_octave: self.slope_db_per_octave,
+            slope_reference: self.slope_reference,
+            slope_fit_range: self.slope_fit_range,
+            magnitude_scale: self.magnitude_scale,
+            floor_db: self.floor_db,
+            reference_channel: self.reference_channel,
+            include_nyquist: self.include_nyquist,
+            smart_resolution: self.smart_crossover,
+            multiresolution: self.multiresolution,
+            amplitude_correction: self.amplitude_correction,
+            silence_threshold: self.silence_threshold_db,
+            frozen: self.frozen,
+        }
+    }
+
+    /// Apply every setting of a snapshot. The configuration and the frequency range are
+    /// validated against the current sample rate first, and nothing is applied if either is
+    /// invalid. Like any change of the FFT size, this reallocates if the FFT size changes.
+    pub fn apply_settings(&mut self, settings: &AnalyzerSettings) -> Result<(), ConfigError> {
+        settings.config.validate(self.sample_rate)?;
+        if let Some((low, high)) = settings.frequency_range {
+            self.check_frequency_range(low, high)?;
+        }
+
+        // These determine the frequency axis, which is recomputed when the configuration is
+        // applied.
+        self.offline = settings.offline;
+        self.include_nyquist = settings.include_nyquist;
+        self.apply_config(settings.config)?;
+        self.channel_overrides.clone_from(&settings.channel_overrides);
+        self.channel_mode = settings.channel_mode;
+        self.frequency_range = settings.frequency_range;
+        self.set_overlap(settings.overlap);
+        self.pending_capacity = settings.pending_capacity;
+        self.set_prefilter_cutoff(settings.prefilter_cutoff);
+        self.phase_output = settings.phase_output;
+        self.set_phase_smoothing(settings.phase_smoothing);
+        match settings.smoothing_time {
+            Some(seconds) => self.set_smoothing_time(seconds),
+            None => self.set_smoothing(settings.smoothing),
+        }
+        self.slope_db_per_octave = settings.slope_db_per_octave;
+        self.slope_reference = settings.slope_reference;
+        self.slope_fit_range = settings.slope_fit_range;
+        self.set_magnitude_scale(settings.magnitude_scale);
+        self.floor_db = settings.floor_db;
+        self.reference_channel = settings.reference_channel;
+        self.smart_crossover = settings.smart_resolution;
+        self.multiresolution = settings.multiresolution;
+        self.amplitude_correction = settings.amplitude_correction;
+        self.silence_threshold_db = settings.silence_threshold;
+        self.frozen = settings.frozen;
+
+        Ok(())
+    }
+
+    /// Apply `settings` for as long as the returned guard lives, and restore the current
+    /// settings when it's dropped. The analyzer is used through the guard in the meantime. This
+    /// is meant for a single offline pass with, for instance, a larger FFT size or more overlap.
+    /// The analysis state, such as the smoothed magnitudes, is not part of the settings and is
+    /// not restored.
+    pub fn with_settings(
+        &mut self,
+        settings: &AnalyzerSettings,
+    ) -> Result<SettingsGuard<'_>, ConfigError> {
+        let previous = self.settings();
+        self.apply_settings(settings)?;
+
+        Ok(SettingsGuard::new(self, previous))
+    }
+
+    /// Get the configuration that applies to the given channel.
+    pub fn channel_config(&self, channel: usize) -> &AnalyzerConfig {
+        match self.channel_overrides.get(channel) {
+            Some(Some(config)) => config,
+            _ => &self.profile_config,
+        }
+    }
+
+    /// Analyze the given channel with its own configuration instead of the global one. Every
+    /// override costs its own window and weighting tables, and an override with a larger FFT size
+    /// than the global configuration grows the channel's scratch buffer and the time spent on
+    /// that channel accordingly.
+    pub fn set_channel_override(&mut self, channel: usize, cfg: AnalyzerConfig) {
+        if self.channel_overrides.len() <= channel {
+            self.channel_overrides.resize(channel + 1, None);
+        }
+
+        self.channel_overrides[channel] = Some(cfg);
+    }
+
+    /// Remove the override for the given channel, so the global configuration applies again.
+    pub fn clear_channel_override(&mut self, channel: usize) {
+        if let Some(channel_override) = self.channel_overrides.get_mut(channel) {
+            *channel_override = None;
+        }
+    }
+
+    /// Get the number of samples in a frame. In the offline profile this is the enlarged size
+    /// that is actually analyzed.
+    pub fn fft_size(&self) -> usize {
+        self.profile_config.fft_size
+    }
+
+    /// Set the number of samples in a frame. In the offline profile the frames are four times
+    /// larger than this, and this size applies again once the profile is turned off. Setting the
+    /// size that's already in use does nothing.
+    pub fn set_fft_size(&mut self, fft_size: usize) {
+        self.config.fft_size = fft_size;
+        let fft_size = self.profile_fft_size(fft_size);
+        if fft_size != self.profile_config.fft_size {
+            self.profile_config.fft_size = fft_size;
+            self.update_frequencies();
+            self.update_smoothing();
+        }
+    }
+
+    /// Get whether the offline profile is active.
+    pub fn offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Switch the offline profile on or off. When rendering offline there is no deadline for a
+    /// block, so the offline profile analyzes frames that are four times larger than the
+    /// configured FFT size, up to [`MAX_FFT_SIZE`], for a finer frequency resolution. The frames
+    /// of [`Analyzer::poll()`] also overlap by at least 75%, for more frames of the same samples.
+    /// Switching it off restores the configured FFT size and overlap. Like any change of the FFT
+    /// size, this reallocates, so it should not be done on the audio thread.
+    pub fn set_offline(&mut self, offline: bool) {
+        self.offline = offline;
+        self.set_fft_size(self.config.fft_size);
+        self.update_smoothing();
+    }
+
+    /// Get the number of samples the analyzer delays the audio it's given by, which a host has to
+    /// compensate for. The analyzer only reads the audio, so this is 0 whatever the FFT size,
+    /// overlap and profile are. The frames it collects delay the results, not the audio.
+    pub fn latency_samples(&self) -> u32 {
+        0
+    }
+
+    /// Get the center frequency in Hz of every bin for the global configuration. The frequency
+    /// axis is the same for every block and every channel without an override, so consumers can
+    /// fetch it once and only read the magnitudes of each [`AnalyzerResult`]. It stays valid
+    /// until the FFT size or the sample rate changes.
+    pub fn frequencies(&self) -> &[f32] {
+        &self.frequencies
+    }
+
+    /// Get the window function that is applied to a frame before it is transformed.
+    pub fn window(&self) -> WindowFunction {
+        self.config.window
+    }
+
+    /// Set the window function that is applied to a frame before it is transformed.
+    pub fn set_window(&mut self, window: WindowFunction) {
+        self.config.window = window;
+        self.profile_config.window = window;
+    }
+
+    /// Get the coherent gain of the window of the global configuration, which is the mean of its
+    /// coefficients. The amplitude correction divides the magnitudes by this.
+    pub fn window_coherent_gain(&self) -> f32 {
+        self.profile_config.window.coherent_gain(self.profile_config.fft_size)
+    }
+
+    /// Get the frequency weighting curve that is applied to the magnitudes.
+    pub fn weighting(&self) -> WeightingCurve {
+        self.config.weighting
+    }
+
+    /// Set the frequency weighting curve that is applied to the magnitudes.
+    pub fn set_weighting(&mut self, weighting: WeightingCurve) {
+        self.config.weighting = weighting;
+        self.profile_config.weighting = weighting;
+    }
+
+    /// Get the frequency range in Hz the results are limited to, or `None` if they cover the
+    /// whole spectrum.
+    pub fn frequency_range(&self) -> Option<(f32, f32)> {
+        self.frequency_range
+    }
+
+    /// Limit the results to the bins from `low` to `high` Hz, inclusive. The frequencies,
+    /// magnitudes and phases of a result then only hold those bins, and its
+    /// [`AnalyzerResult::start_bin`] tells where they start in the full analysis. The whole
+    /// spectrum is still analyzed, so the smoothing and [`Analyzer::slope_deviation()`] are not
+    /// affected. The range must be increasing and within zero and the Nyquist frequency, and is
+    /// not applied otherwise.
+    pub fn set_frequency_range(&mut self, low: f32, high: f32) -> Result<(), ConfigError> {
+        self.check_frequency_range(low, high)?;
+        self.frequency_range = Some((low, high));
+        Ok(())
+    }
+
+    /// Let the results cover the whole spectrum again.
+    pub fn clear_frequency_range(&mut self) {
+        self.frequency_range = None;
+    }
+
+    /// Get how the channels of a buffer are turned into analyzed signals.
+    pub fn channel_mode(&self) -> ChannelMode {
+        self.channel_mode
+    }
+
+    /// Set how the channels of a buffer are turned into analyzed signals.
+    pub fn set_channel_mode(&mut self, channel_mode: ChannelMode) {
+        self.channel_mode = channel_mode;
+    }
+
+    /// Get whether the phase spectrum is included in the results.
+    pub fn phase_output(&self) -> bool {
+        self.phase_output
+    }
+
+    /// Set whether the phase spectrum is included in the results. This is disabled by default so
+    /// consumers that only need the magnitudes don't pay for it.
+    pub fn set_phase_output(&mut self, enabled: bool) {
+        self.phase_output = enabled;
+    }
+
+    /// Get whether the results include the Nyquist bin.
+    pub fn include_nyquist(&self) -> bool {
+        self.include_nyquist
+    }
+
+    /// Set whether the results include the Nyquist bin. When enabled the magnitudes and
+    /// frequencies have `fft_size / 2 + 1` entries, and the last one is at half the sample rate.
+    /// This is disabled by default, which gives `fft_size / 2` entries.
+    pub fn set_include_nyquist(&mut self, enabled: bool) {
+        self.include_nyquist = enabled;
+        self.update_frequencies();
+    }
+
+    /// Make [`Analyzer::process()`] return `result` as its only result, no matter what the buffer
+    /// contains. This is meant for developing and testing the GUI without live audio, and for
+    /// visual regression tests that need a known spectrum. It only exists in debug builds, so it
+    /// can't be left on in a release build.
+    #[cfg(debug_assertions)]
+    pub fn set_synthetic(&mut self, result: AnalyzerResult) {
+        self.synthetic = Some(result);
+    }
+
+    /// Go back to analyzing the buffers passed to [`Analyzer::process()`].
+    #[cfg(debug_assertions)]
+    pub fn clear_synthetic(&mut self) {
+        self.synthetic = None;
+    }
+
+    /// Get the crossover frequency in Hz of the smart resolution mode, or `None` if it's disabled.
+    pub fn smart_resolution(&self) -> Option<f32> {
+        self.smart_crossover
+    }
+
+    /// Enable the smart resolution mode with the given crossover frequency in Hz, or disable it
+    /// by passing `0.0`. In this mode every channel is analyzed twice: with the configured FFT
+    /// size for the fine frequency resolution the low end needs, and with a four times smaller
+    /// FFT over the most recent part of the frame for a faster response in the high end. The
+    /// two are blended over the octave centered on the crossover, with the weight of the small
+    /// FFT rising linearly with the logarithm of the frequency. The result keeps the bins of the
+    /// large FFT, and the small FFT's magnitudes are scaled so a sinusoid reads the same in
+    /// both. The latency is still governed by the larger FFT.
+    pub fn set_smart_resolution(&mut self, crossover_hz: f32) {
+        self.smart_crossover = (crossover_hz > 0.0).then_some(crossover_hz);
+    }
+
+    /// Get whether the multiresolution mode is enabled.
+    pub fn multiresolution(&self) -> bool {
+        self.multiresolution
+    }
+
+    /// Enable or disable the multiresolution mode. Low frequencies need a long frame to be
+    /// resolved while high frequencies don't, so in this mode every channel is also analyzed
+    /// after low-pass filtering and decimating it by four, which gives four times the resolution
+    /// with the same FFT size. That analysis covers the bins below an eighth of the Nyquist
+    /// frequency, and the regular analysis covers the rest. The result's frequencies are still
+    /// increasing, but are no longer evenly spaced, and there are `fft_size / 4` more of them
+    /// than without this mode. The decimated analysis uses the most recent `4 * fft_size`
+    /// samples, so the latency of the low frequencies grows accordingly. Phase output is not
+    /// available in this mode.
+    pub fn set_multiresolution(&mut self, enabled: bool) {
+        self.multiresolution = enabled;
+    }
+
+    /// Get whether the results are held instead of being updated.
+    pub fn frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// Hold the current results, for instance to examine a moment in the display. While frozen,
+    /// [`Analyzer::process()`] keeps returning the last computed results without analyzing the
+    /// new blocks, and [`Analyzer::poll()`] still consumes the queued frames, so the analysis
+    /// picks up with the current audio as soon as this is turned off again.
+    pub fn set_frozen(&mut self, frozen: bool) {
+        self.frozen = frozen;
+    }
+
+    /// Get the level in dB below which a block is considered silent.
+    pub fn silence_threshold(&self) -> f32 {
+        self.silence_threshold_db
+    }
+
+    /// Skip the analysis of blocks whose peak level on every channel is below `db`, to save CPU
+    /// on silent tracks. The FFT of such a block would be all but zero, so instead the previous
+    /// results are decayed the way the smoothing would, and returned again. This only kicks in
+    /// once there are results to decay. The default of negative infinity analyzes every block.
+    pub fn set_silence_threshold(&mut self, db: f32) {
+        self.silence_threshold_db = db;
+    }
+
+    /// Get the number of consecutive blocks that were skipped for being silent, see
+    /// [`Analyzer::set_silence_threshold()`]. This is zero after a block that was analyzed.
+    pub fn silent_blocks(&self) -> usize {
+        self.silent_blocks
+    }
+
+    /// Get the number of frames results were produced for since the analyzer was created or
+    /// reset. Every analyzed block counts as one frame, and so do the silent blocks whose
+    /// results were decayed instead. Blocks that arrive while the results are frozen don't count.
+    pub fn frames_processed(&self) -> u64 {
+        self.frames_processed
+    }
+
+    /// Get the number of frames that were lost since the analyzer was created or reset, because
+    /// the queues of [`Analyzer::push_samples()`] were full and discarded their oldest samples,
+    /// see [`Analyzer::set_pending_capacity()`]. A frame of [`Analyzer::poll()`] starts every hop
+    /// of [`Analyzer::overlap()`], so this counts the whole hops in the discarded samples of the
+    /// channel that discarded the most.
+    pub fn frames_dropped(&self) -> u64 {
+        self.frames_dropped
+    }
+
+    /// Get whether the magnitudes are normalized by the frame length.
+    pub fn amplitude_correction(&self) -> bool {
+        self.amplitude_correction
+    }
+
+    /// Set whether the magnitudes are normalized by the frame length and the window. Without
+    /// this correction the magnitudes grow with the FFT size and shrink with the window's
+    /// coherent gain. With it, which is the default, every magnitude is divided by the FFT size
+    /// and by [`Analyzer::window_coherent_gain()`], and all bins other than DC and Nyquist are
+    /// doubled to account for the discarded negative frequencies, so a full-scale sine that falls
+    /// on a bin reads about `1.0` regardless of the FFT size and the window.
+    pub fn set_amplitude_correction(&mut self, enabled: bool) {
+        self.amplitude_correction = enabled;
+    }
+
+    /// Get the width in octaves of the phase smoothing window.
+    pub fn phase_smoothing(&self) -> f32 {
+        self.phase_smoothing
+    }
+
+    /// Set the width in octaves of the window the phase spectrum is smoothed over, or `0.0` to
+    /// disable phase smoothing. The phase is unwrapped first and then smoothed, so the smoothing
+    /// doesn't average across the jumps between -π and π. This is independent of any smoothing
+    /// of the magnitudes.
+    pub fn set_phase_smoothing(&mut self, octaves: f32) {
+        self.phase_smoothing = octaves.max(0.0);
+    }
+
+    /// Get how much of the previous frame's magnitudes is kept in every new frame. If the
+    /// smoothing was set as a time, this is the coefficient of the last frame that was analyzed,
+    /// see [`Analyzer::set_smoothing_time()`].
+    pub fn smoothing(&self) -> f32 {
+        self.smoothing
+    }
+
+    /// Get the cutoff frequency in Hz of the high-pass pre-filter, or `0.0` if it's disabled.
+    pub fn prefilter_cutoff(&self) -> f32 {
+        self.prefilter_cutoff
+    }
+
+    /// Filter the samples with a one-pole high-pass filter at `hz` before they are windowed, or
+    /// pass `0.0` to disable the filter. Unlike a weighting curve, which only scales the
+    /// magnitudes, this removes DC drift and low rumble from the signal, so their energy doesn't
+    /// leak into the bins around them. The filter's state is kept per channel from one block to
+    /// the next, and is cleared by [`Analyzer::reset()`].
+    pub fn set_prefilter_cutoff(&mut self, hz: f32) {
+        self.prefilter_cutoff = hz.max(0.0);
+    }
+
+    /// Get the fraction of a frame that consecutive frames of [`Analyzer::poll()`] share. In the
+    /// offline profile they may share more, see [`Analyzer::set_offline()`].
+    pub fn overlap(&self) -> f32 {
+        self.overlap
+    }
+
+    /// Let consecutive frames of [`Analyzer::poll()`] share the fraction `overlap` of their
+    /// samples, so a frame starts every `(1 - overlap) * fft_size` samples instead of every
+    /// `fft_size` samples. More overlap gives more frames for the same samples, which makes up
+    /// for the samples the window attenuates at the edges of every frame. The value is clamped to
+    /// `[0, MAX_OVERLAP]`.
+    pub fn set_overlap(&mut self, overlap: f32) {
+        self.overlap = overlap.clamp(0.0, MAX_OVERLAP);
+        self.update_smoothing();
+    }
+
+    /// Smooth the magnitudes over time, by blending every frame with the previous one. Every bin
+    /// becomes `smoothing * previous + (1 - smoothing) * current`, so `0.0` disables the
+    /// smoothing and values closer to `1.0` make the spectrum respond more slowly. The value is
+    /// clamped to `[0, 1]`.
+    ///
+    /// How quickly this responds depends on how often frames are analyzed. Use
+    /// [`Analyzer::set_smoothing_time()`] for smoothing that doesn't change with the sample rate.
+    pub fn set_smoothing(&mut self, smoothing: f32) {
+        self.smoothing = smoothing.clamp(0.0, 1.0);
+        self.smoothing_time = None;
+    }
+
+    /// Get the time constant in seconds of the smoothing, or `None` if the smoothing was set as
+    /// a coefficient with [`Analyzer::set_smoothing()`].
+    pub fn smoothing_time(&self) -> Option<f32> {
+        self.smoothing_time
+    }
 
-            let frequencies = (0..fft_size / 2)
-                .map(|i| i as f32 * self.sample_rate / fft_size as f32)
-                .collect::<Vec<_>>();
+    /// Smooth the magnitudes over time with a time constant of `seconds`: after a step in the
+    /// input, the smoothed magnitudes have covered all but `1 / e` of the step after that time.
+    /// The coefficient of [`Analyzer::set_smoothing()`] is derived from the time between two
+    /// frames, which is the hop of [`Analyzer::poll()`], or the length of the block for
+    /// [`Analyzer::process()`], which analyzes one frame per block. It's derived again whenever
+    /// that time or the sample rate changes, so the smoothing looks the same at any sample rate
+    /// and block size. `0.0` disables the smoothing.
+    pub fn set_smoothing_time(&mut self, seconds: f32) {
+        self.smoothing_time = Some(seconds.max(0.0));
+        self.update_smoothing();
+    }
 
-            results.push(AnalyzerResult { magnitudes, frequencies });
+    /// Get the number of frames it takes the smoothed magnitudes to decay by 60 dB once the input
+    /// goes silent, which is how long the display keeps changing after that. This is `Some(0)`
+    /// when smoothing is disabled, and `None` when the smoothing is so strong the magnitudes
+    /// never decay.
+    pub fn smoothing_decay_frames(&self) -> Option<usize> {
+        if self.smoothing == 0.0 {
+            return Some(0);
         }
+        if self.smoothing >= 1.0 {
+            return None;
+        }
+
+        let decay_per_frame_db = -20.0 * self.smoothing.log10();
+        Some((DECAY_RANGE_DB / decay_per_frame_db).ceil() as usize)
+    }
+
+    /// Get the number of samples the decay of [`Analyzer::smoothing_decay_frames()`] takes when a
+    /// frame starts every hop of [`Analyzer::poll()`]. This follows from the FFT size, overlap and
+    /// profile at the current sample rate, not from the size of the blocks the audio arrives in.
+    pub fn smoothing_decay_samples(&self) -> Option<usize> {
+        let hop_size = self.hop_size(self.profile_config.fft_size);
+        self.smoothing_decay_frames().map(|frames| frames * hop_size)
+    }
+
+    /// Get the tilt in dB per octave that is applied to the magnitudes.
+    pub fn slope_db_per_octave(&self) -> f32 {
+        self.slope_db_per_octave
+    }
+
+    /// Tilt the spectrum by `slope` dB per octave around the slope reference frequency, after
+    /// the magnitudes are computed. With a positive slope the bins above the reference are
+    /// boosted and the bins below it are attenuated, so a slope of +3 dB per octave makes pink
+    /// noise look flat. The DC bin is left as is. `0.0` disables the tilt.
+    pub fn set_slope_db_per_octave(&mut self, slope: f32) {
+        self.slope_db_per_octave = slope;
+    }
+
+    /// Get the frequency in Hz the tilt is anchored at.
+    pub fn slope_reference(&self) -> f32 {
+        self.slope_reference
+    }
+
+    /// Set the frequency in Hz the tilt is anchored at, which defaults to 1 kHz. Bins at this
+    /// frequency are not affected by the tilt.
+    pub fn set_slope_reference(&mut self, frequency: f32) {
+        self.slope_reference = frequency;
+    }
 
-        results
+    /// Get whether the magnitudes are amplitudes or powers.
+    pub fn magnitude_scale(&self) -> MagnitudeScale {
+        self.magnitude_scale
     }
-}
\ No newline at end of file
+
+    /// Set whether the magnitudes are amplitudes or powers, see [`MagnitudeScale`]. Defaults to
+    /// [`MagnitudeScale::Linear`]. The smoothed magnitudes of the previous frames are forgotten,
+    /// as they are on the other scale.
+    pub fn set_magnitude_scale(&mut self, scale: MagnitudeScale) {
+        if scale != self.magnitude_scale {
+            self.magnitude_scale = scale;
+            for state in &mut self.channels {
+                state.smoothed.clear();
+            }
+        }
+    }
+
+    /// Get the lowest level in dB [`Analyzer::process_db()`] reports.
+    pub fn floor_db(&self) -> f32 {
+        self.floor_db
+    }
+
+    /// Set the lowest level in dB [`Analyzer::process_db()`] reports, which defaults to
+    /// [`DEFAULT_FLOOR_DB`]. Quieter bins, including bins without any energy, are reported at the
+    /// floor, so the levels never contain negative infinity. The floor only applies to the dB
+    /// conversion: the smoothing and averaging work on the linear magnitudes, which are left as
+    /// they are.
+    pub fn set_floor_db(&mut self, floor: f32) {
+        self.floor_db = floor;
+    }
+
+    /// Get the frequency range in Hz used by [`Analyzer::slope_deviation()`].
+    pub fn slope_fit_range(&self) -> (f32, f32) {
+        self.slope_fit_range
+    }
+
+    /// Set the frequency range in Hz used by [`Analyzer::slope_deviation()`]. Defaults to 100 Hz
+    /// to 10 kHz, which leaves out the sub-bass and the top octave where most mixes roll off.
+    pub fn set_slope_fit_range(&mut self, low: f32, high: f32) {
+        self.slope_fit_range = (low, high);
+    }
+
+    /// Get how much the slope of the last analyzed spectrum deviates from the target slope in
+    /// dB per octave. The slope is measured by a least-squares fit of a line through the
+    /// magnitudes in dB against the frequencies in octaves, using the channel-averaged spectrum
+    /// within [`Analyzer::slope_fit_range()`]. A positive value means the spectrum is brighter
+    /// than the target, a negative value means it is darker. Returns `0.0` if there are not
+    /// enough bins in the fit range to fit a line through.
+    pub fn slope_deviation(&self, target_db_per_oct: f32) -> f32 {
+        let (low, high) = self.slope_fit_range;
+        let mut count = 0.0;
+        let mut sum_x = 0.0;
+        let mut sum_y = 0.0;
+        let mut sum_xx = 0.0;
+        let mut sum_xy = 0.0;
+        for (i, &magnitude) in self.average.iter().enumerate() {
+            let frequency = i as f32 * self.average_bin_width;
+            if frequency < low || frequency > high || magnitude <= 0.0 {
+                continue;
+            }
+
+            let x = frequency.log2();
+            let y = 20.0 * magnitude.log10();
+            count += 1.0;
+            sum_x += x;
+            sum_y += y;
+            sum_xx += x * x;
+            sum_xy += x * y;
+        }
+
+        let denominator = count * sum_xx - sum_x * sum_x;
+        if count < 2.0 || denominator.abs() <= f32::EPSILON {
+            return 0.0;
+        }
+
+        let slope = (count * sum_xy - sum_x * sum_y) / denominator;
+        slope - target_db_per_oct
+    }
+
+    /// Get the channel that [`Analyzer::relative_group_delay()`] measures against.
+    pub fn reference_channel(&self) -> Option<usize> {
+        self.reference_channel
+    }
+
+    /// Set the channel that [`Analyzer::relative_group_delay()`] measures against, or `None` to
+    /// disable the measurement.
+    pub fn set_reference_channel(&mut self, channel: Option<usize>) {
+        self.reference_channel = channel;
+    }
+
+    /// Get the group delay in seconds of the main channel relative to the reference channel for
+    /// every bin of the last processed block, which is what needs to be compensated to align two
+    /// speakers around their crossover. The main channel is the first channel, or the second one
+    /// if the first channel is the reference. The difference between the two channels' phases is
+    /// unwrapped and differentiated over frequency, so a positive delay means the main channel
+    /// lags the reference. Returns `None` if there is no reference channel, if either channel
+    /// wasn't part of the last block, if the two channels don't share a frequency axis, or if
+    /// phase output is disabled.
+    pub fn relative_group_delay(&self) -> Option<Vec<f32>> {
+        let reference_channel = self.reference_channel?;
+        let main_channel = if reference_channel == 0 { 1 } else { 0 };
+        let main = self.results.get(main_channel)?;
+        let reference = self.results.get(reference_channel)?;
+        let (main_phases, reference_phases) = (main.phases.as_ref()?, reference.phases.as_ref()?);
+        if main.frequencies != reference.frequencies || main.frequencies.len() < 2 {
+            return None;
+        }
+
+        // The phases may already be unwrapped, so the difference is wrapped again before it is
+        // unwrapped as a whole.
+        let mut phase_difference = main_phases
+            .iter()
+            .zip(reference_phases)
+            .map(|(main, reference)| {
+                let difference = main - reference;
+                difference - std::f32::consts::TAU * (difference / std::f32::consts::TAU).round()
+            })
+            .collect::<Vec<_>>();
+        unwrap_phase(&mut phase_difference);
+
+        let angular_bin_width = std::f32::consts::TAU * (main.frequencies[1] - main.frequencies[0]);
+        let last = phase_difference.len() - 1;
+        let delays = (0..=last)
+            .map(|bin| {
+                let (below, above) = (bin.saturating_sub(1), (bin + 1).min(last));
+                let slope = (phase_difference[above] - phase_difference[below])
+                    / ((above - below) as f32 * angular_bin_width);
+                -slope
+            })
+            .collect();
+
+        Some(delays)
+    }
+
+    /// Start measuring an impulse response with the given sweep. Play the sweep through the
+    /// measured system with [`Analyzer::play_sweep()`], pass what comes back to
+    /// [`Analyzer::record_sweep_response()`], and call [`Analyzer::capture_impulse_response()`]
+    /// once the sweep and the tail of the system have been recorded. Starting a new capture
+    /// discards the one in progress.
+    pub fn start_sweep_capture(&mut self, sweep: ExponentialSweep) {
+        self.sweep_capture = Some(SweepCapture::new(&sweep, self.sample_rate));
+    }
+
+    /// Write the next samples of the sweep to `output`. The output is silent once the whole
+    /// sweep has been played, or if no capture has been started.
+    pub fn play_sweep(&mut self, output: &mut [f32]) {
+        match &mut self.sweep_capture {
+            Some(capture) => capture.play(output),
+            None => output.fill(0.0),
+        }
+    }
+
+    /// Record the response of the measured system to the sweep.
+    pub fn record_sweep_response(&mut self, input: &[f32]) {
+        if let Some(capture) = &mut self.sweep_capture {
+            capture.record(input);
+        }
+    }
+
+    /// Finish the capture that was started with [`Analyzer::start_sweep_capture()`], and
+    /// deconvolve the recorded response into the impulse response and frequency response of the
+    /// measured system. The deconvolution convolves the recording with the sweep's inverse
+    /// filter in the frequency domain. Returns an empty response if nothing was recorded.
+    pub fn capture_impulse_response(&mut self) -> ImpulseResponse {
+        match self.sweep_capture.take() {
+            Some(capture) => capture.impulse_response(&mut self.fft_planner, self.sample_rate),
+            None => ImpulseResponse::default(),
+        }
+    }
+
+    /// Get the spectral centroid of a result in Hz, which is the magnitude-weighted mean of the
+    /// frequencies. This works on whatever magnitudes the result holds. Returns `0.0` if the
+    /// total magnitude is zero.
+    pub fn spectral_centroid(result: &AnalyzerResult) -> f32 {
+        let mut weighted_sum = 0.0;
+        let mut magnitude_sum = 0.0;
+        for (frequency, magnitude) in result.frequencies.iter().zip(&result.magnitudes) {
+            weighted_sum += frequency * magnitude;
+            magnitude_sum += magnitude;
+        }
+
+        if magnitude_sum == 0.0 {
+            0.0
+        } else {
+            weighted_sum / magnitude_sum
+        }
+    }
+
+    /// Get the spectral rolloff of a result in Hz, which is the frequency of the first bin at
+    /// which the energy of the bins up to and including it reaches `percentage` of the total
+    /// energy, normally [`DEFAULT_ROLLOFF_PERCENTAGE`]. The energy of a bin is its squared
+    /// magnitude, and `percentage` is clamped to (0, 1]. Returns `0.0` if the result has no
+    /// energy.
+    pub fn spectral_rolloff(result: &AnalyzerResult, percentage: f32) -> f32 {
+        let percentage = percentage.clamp(f32::MIN_POSITIVE, 1.0);
+        let total = result.magnitudes.iter().map(|magnitude| magnitude * magnitude).sum::<f32>();
+        if total <= 0.0 {
+            return 0.0;
+        }
+
+        let threshold = percentage * total;
+        let mut energy = 0.0;
+        for (&frequency, magnitude) in result.frequencies.iter().zip(&result.magnitudes) {
+            energy += magnitude * magnitude;
+            if energy >= threshold {
+                return frequency;
+            }
+        }
+
+        // Rounding can leave the running sum just short of the total.
+        result.frequencies.last().copied().unwrap_or(0.0)
+    }
+
+    /// Get the spectral flatness (Wiener entropy) of a result, which is the ratio of the geometric
+    /// mean to the arithmetic mean of the magnitudes. This is close to `1.0` for white noise and
+    /// close to `0.0` for a pure tone. Returns `0.0` if the result has no energy.
+    pub fn spectral_flatness(result: &AnalyzerResult) -> f32 {
+        // Keeps zero magnitudes from sending the logarithm to negative infinity.
+        const EPSILON: f32 = 1e-12;
+
+        if result.magnitudes.is_empty() {
+            return 0.0;
+        }
+
+        let count = result.magnitudes.len() as f32;
+        let mut log_sum = 0.0;
+        let mut sum = 0.0;
+        for &magnitude in &result.magnitudes {
+            log_sum += (magnitude.max(0.0) + EPSILON).ln();
+            sum += magnitude;
+        }
+
+        let arithmetic_mean = sum / count;
+        if arithmetic_mean <= 0.0 {
+            return 0.0;
+        }
+
+        let geometric_mean = (log_sum / count).exp();
+        geometric_mean / arithmetic_mean
+    }
+
+    /// Get the frequency in Hz and the magnitude of the strongest bin of a result. When the peak
+    /// has a neighbour on both sides, its position and height are refined by fitting a parabola
+    /// through the logarithms of the three magnitudes, which gives sub-bin accuracy. Returns
+    /// `None` if all magnitudes are zero.
+    pub fn dominant_peak(result: &AnalyzerResult) -> Option<(f32, f32)> {
+        let (peak_bin, &peak) = result
+            .magnitudes
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+        if peak <= 0.0 {
+            return None;
+        }
+
+        let frequency = result.frequencies[peak_bin];
+        let (previous, next) = match (
+            peak_bin.checked_sub(1).map(|bin| result.magnitudes[bin]),
+            result.magnitudes.get(peak_bin + 1),
+        ) {
+            (Some(previous), Some(&next)) if previous > 0.0 && next > 0.0 => (previous, next),
+            _ => return Some((frequency, peak)),
+        };
+
+        let (offset, log_magnitude) =
+            Self::parabolic_interpolate(previous.ln(), peak.ln(), next.ln());
+        let bin_width = result.frequencies[1] - result.frequencies[0];
+        Some((frequency + offset * bin_width, log_magnitude.exp()))
+    }
+
+    /// Fit a parabola through the values of three neighbouring bins and get the position of its
+    /// vertex relative to the middle bin in bins, between -0.5 and 0.5 when the middle bin is the
+    /// largest, and the value at the vertex. This locates a peak with sub-bin accuracy. Fitting
+    /// the logarithms of the magnitudes, like [`Analyzer::dominant_peak()`] does, is more
+    /// accurate than fitting the magnitudes themselves. Returns `(0.0, y_zero)` if the three
+    /// values lie on a line.
+    pub fn parabolic_interpolate(y_minus: f32, y_zero: f32, y_plus: f32) -> (f32, f32) {
+        let denominator = y_minus - 2.0 * y_zero + y_plus;
+        if denominator == 0.0 {
+            return (0.0, y_zero);
+        }
+
+        let offset = 0.5 * (y_minus - y_plus) / denominator;
+        (offset, y_zero - 0.25 * (y_minus - y_plus) * offset)
+    }
+
+    /// Estimate the noise floor of a result in dB, as the median of its magnitudes. Unlike the
+    /// mean, the median is hardly affected by a few strong tones, so this tracks the background
+    /// noise as long as tones occupy less than half of the bins. Returns negative infinity if the
+    /// result has no bins.
+    pub fn noise_floor(result: &AnalyzerResult) -> f32 {
+        if result.magnitudes.is_empty() {
+            return f32::NEG_INFINITY;
+        }
+
+        let mut magnitudes = result.magnitudes.clone();
+        let middle = magnitudes.len() / 2;
+        let (_, median, _) = magnitudes.select_nth_unstable_by(middle, |a, b| a.total_cmp(b));
+        20.0 * median.log10()
+    }
+
+    /// Get the total harmonic distortion of a test tone as a percentage: the RMS of the first
+    /// `num_harmonics` harmonics above the fundamental relative to the fundamental. Every
+    /// component is snapped to its nearest bin, and the power of the two bins on either side is
+    /// included to account for the leakage of the window. Harmonics above the highest bin are
+    /// ignored. Returns `0.0` if the fundamental is outside the result or has no energy.
+    pub fn thd(result: &AnalyzerResult, fundamental_hz: f32, num_harmonics: usize) -> f32 {
+        const NEIGHBOURHOOD: usize = 2;
+
+        if result.frequencies.len() < 2 {
+            return 0.0;
+        }
+
+        let bin_width = result.frequencies[1] - result.frequencies[0];
+        let component_power = |frequency: f32| {
+            let bin = (frequency / bin_width).round() as usize;
+            if bin >= result.magnitudes.len() {
+                return None;
+            }
+
+            let low = bin.saturating_sub(NEIGHBOURHOOD);
+            let high = (bin + NEIGHBOURHOOD).min(result.magnitudes.len() - 1);
+            Some(result.magnitudes[low..=high].iter().map(|magnitude| magnitude * magnitude).sum())
+        };
+
+        let fundamental_power: f32 = match component_power(fundamental_hz) {
+            Some(power) if power > 0.0 => power,
+            _ => return 0.0,
+        };
+        let harmonic_power = (2..=num_harmonics + 1)
+            .map_while(|harmonic| component_power(fundamental_hz * harmonic as f32))
+            .sum::<f32>();
+
+        100.0 * (harmonic_power / fundamental_power).sqrt()
+    }
+
+    /// Fold a result into the 12 pitch classes, starting at C. Every bin is mapped to its nearest
+    /// equal tempered note relative to `reference_a4`, which is normally
+    /// [`DEFAULT_REFERENCE_A4`], and its magnitude is added to that note's pitch class. Bins
+    /// below the lowest audible note, C0 at about 16 Hz with standard tuning, are skipped.
+    pub fn chromagram(result: &AnalyzerResult, reference_a4: f32) -> [f32; 12] {
+        // C0 is MIDI note 12, and A4 is MIDI note 69.
+        const LOWEST_NOTE: f32 = 12.0;
+
+        let mut chroma = [0.0; 12];
+        for (&frequency, &magnitude) in result.frequencies.iter().zip(&result.magnitudes) {
+            if frequency <= 0.0 {
+                continue;
+            }
+
+            let note = (69.0 + 12.0 * (frequency / reference_a4).log2()).round();
+            if note < LOWEST_NOTE {
+                continue;
+            }
+
+            chroma[note as usize % 12] += magnitude;
+        }
+
+        chroma
+    }
+
+    /// Get the magnitude of `samples` at exactly `target_hz` with the Goertzel algorithm, at the
+    /// analyzer's sample rate. This evaluates a single DFT bin at an arbitrary frequency in one
+    /// pass over the samples, without planning or running an FFT, which makes it the cheaper
+    /// choice for monitoring a handful of known frequencies such as a line-up tone or mains hum.
+    /// No window is applied, and the magnitude is scaled so a sine with an amplitude of `A` at
+    /// the target frequency reads about `A` when the samples hold a whole number of its periods.
+    /// Returns `0.0` for an empty slice.
+    pub fn goertzel(&self, samples: &[f32], target_hz: f32) -> f32 {
+        if samples.is_empty() {
+            return 0.0;
+        }
+
+        let coefficient = 2.0 * (2.0 * std::f32::consts::PI * target_hz / self.sample_rate).cos();
+        let (mut previous, mut before_previous) = (0.0_f32, 0.0_f32);
+        for &sample in samples {
+            let current = sample + coefficient * previous - before_previous;
+            before_previous = previous;
+            previous = current;
+        }
+
+        let power = previous * previous + before_previous * before_previous
+            - coefficient * previous * before_previous;
+        2.0 * power.max(0.0).sqrt() / samples.len() as f32
+    }
+
+    /// Allocate everything [`Analyzer::process()`] needs for buffers of up to `channel_count`
+    /// channels and `max_block_size` samples with the current configuration. Call this before
+    /// processing on the audio thread, so the first blocks don't allocate.
+    pub fn prepare(&mut self, channel_count: usize, max_block_size: usize) {
+        if self.channels.len() < channel_count {
+            self.channels.resize_with(channel_count, ChannelState::default);
+        }
+        self.results.resize_with(channel_count.max(self.results.len()), AnalyzerResult::default);
+
+        let mut max_bin_count = 0;
+        for channel in 0..channel_count {
+            let config = *self.channel_config(channel);
+            self.channels[channel].prepare(&config, self.sample_rate, &mut self.fft_backend);
+            self.channels[channel].filtered.reserve(max_block_size);
+
+            let bin_count = result_capacity(config.fft_size);
+            let result = &mut self.results[channel];
+            result.magnitudes.reserve(bin_count);
+            result.frequencies.reserve(bin_count);
+            if self.phase_output {
+                result.phases.get_or_insert_with(Vec::new).reserve(bin_count);
+            }
+            max_bin_count = max_bin_count.max(bin_count);
+        }
+
+        self.average.reserve(max_bin_count);
+        self.mixed.resize_with(2, Vec::new);
+        for mixed in &mut self.mixed {
+            mixed.reserve(max_block_size);
+        }
+    }
+
+    /// Plan the FFTs and make room for every FFT size in `fft_sizes` ahead of time for the first
+    /// `channel_count` channels, so [`Analyzer::set_fft_size()`] can switch between them on the
+    /// audio thread without planning or allocating. This is meant to be called after
+    /// [`Analyzer::prepare()`], with every size a host can choose. In the offline profile the
+    /// enlarged sizes are planned, so this needs to be called again after switching the profile.
+    pub fn prepare_fft_sizes(&mut self, channel_count: usize, fft_sizes: &[usize]) {
+        if self.channels.len() < channel_count {
+            self.channels.resize_with(channel_count, ChannelState::default);
+        }
+        self.results.resize_with(channel_count.max(self.results.len()), AnalyzerResult::default);
+
+        for &fft_size in fft_sizes {
+            let fft_size = self.profile_fft_size(fft_size);
+            for state in &mut self.channels[..channel_count] {
+                state.prepare_fft_size(fft_size, &mut self.fft_backend);
+            }
+            self.max_prepared_fft_size = self.max_prepared_fft_size.max(fft_size);
+        }
+
+        let bin_count = result_capacity(self.max_prepared_fft_size);
+        for result in &mut self.results[..channel_count] {
+            reserve_total(&mut result.magnitudes, bin_count);
+            reserve_total(&mut result.frequencies, bin_count);
+            if self.phase_output {
+                reserve_total(result.phases.get_or_insert_with(Vec::new), bin_count);
+            }
+        }
+        reserve_total(&mut self.frequencies, bin_count);
+        reserve_total(&mut self.average, bin_count);
+    }
+
+    /// Get the most bins a result of one of the first `channel_count` channels can have with the
+    /// current configuration, or with any of the FFT sizes prepared by
+    /// [`Analyzer::prepare_fft_sizes()`]. This is what those and [`Analyzer::prepare()`] reserve
+    /// room for.
+    pub fn max_bin_count(&self, channel_count: usize) -> usize {
+        (0..channel_count)
+            .map(|channel| result_capacity(self.channel_config(channel).fft_size))
+            .chain((channel_count > 0).then(|| result_capacity(self.max_prepared_fft_size)))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Clear everything left over from earlier audio: the samples queued with
+    /// [`Analyzer::push_samples()`], the smoothing history, the previous results, the frame
+    /// counters and a sweep capture in progress. The configuration is kept, and so are the
+    /// allocations made by [`Analyzer::prepare()`].
+    pub fn reset(&mut self) {
+        for queue in &mut self.pending {
+            queue.clear();
+        }
+        self.discarded.clear();
+        for result in &mut self.results {
+            result.frequencies.clear();
+            result.magnitudes.clear();
+            if let Some(phases) = &mut result.phases {
+                phases.clear();
+            }
+        }
+        for channel in &mut self.channels {
+            channel.smoothed.clear();
+            channel.prefilter_state = (0.0, 0.0);
+        }
+        self.average.clear();
+        self.sweep_capture = None;
+        self.transfer = None;
+        self.reported_length_mismatch = false;
+        self.silent_blocks = 0;
+        self.frames_processed = 0;
+        self.frames_dropped = 0;
+    }
+
+    /// Process the buffer and analyze the spectrum. The results are written into buffers owned
+    /// by the analyzer, which are only reallocated when the FFT size or the number of channels
+    /// grows, so this doesn't allocate in the steady state. The returned results stay valid until
+    /// the next call. The buffer's samples are only read, so in a plugin, where the buffer is
+    /// processed in place, the audio passes through unchanged.
+    ///
+    /// All channels must be [`Buffer::samples()`] long. If they aren't, the buffer is not
+    /// analyzed and no results are returned. This is logged the first time it happens. A buffer
+    /// without samples isn't analyzed either, and leaves the previous results as they are. Any
+    /// shorter buffer, down to a single sample, is padded with zeros to a full frame.
+    pub fn process(&mut self, buffer: &mut Buffer) -> &[AnalyzerResult] {
+        #[cfg(debug_assertions)]
+        if self.synthetic.is_some() {
+            return self.synthetic.as_slice();
+        }
+
+        let sample_count = buffer.samples();
+        if sample_count == 0 {
+            return &[];
+        }
+        let channels = buffer.as_slice_immutable();
+        if !self.check_channel_lengths(channels, sample_count) {
+            return &[];
+        }
+
+        // Every block is a frame of its own, so frames are a block apart.
+        self.analyze_block(channels, sample_count, sample_count);
+
+        &self.results
+    }
+
+    /// Process the buffer like [`Analyzer::process()`], and copy the results into `out` instead
+    /// of returning them, for hosts that keep their own results. The vectors of every result in
+    /// `out` are reused, so once they've grown to the size of the analysis nothing is allocated.
+    /// Returns the number of results that were written, which is at most `out.len()`. Results
+    /// that don't fit are left out, and the results in `out` past the returned count are left
+    /// as they are.
+    pub fn process_into(&mut self, buffer: &mut Buffer, out: &mut [AnalyzerResult]) -> usize {
+        let results = self.process(buffer);
+        for (target, result) in out.iter_mut().zip(results) {
+            target.clone_from(result);
+        }
+
+        results.len().min(out.len())
+    }
+
+    /// Process the buffer and get the full complex spectrum of every channel, for work that
+    /// needs more than the magnitudes, such as cross-spectra or cepstra. The frame is windowed
+    /// and transformed exactly like in [`Analyzer::process()`], with the same cached window and
+    /// FFT plan, but the output isn't folded, so every spectrum is as long as the channel's FFT
+    /// size: bin `k` above the Nyquist bin is the complex conjugate of bin `fft_size - k`. No
+    /// weighting, amplitude correction, slope or smoothing is applied, and the results of
+    /// [`Analyzer::process()`] are left as they are.
+    ///
+    /// Like [`Analyzer::process()`], this returns nothing if the channels are not all
+    /// [`Buffer::samples()`] long.
+    pub fn process_complex(&mut self, buffer: &mut Buffer) -> Vec<Vec<Complex<f32>>> {
+        let sample_count = buffer.samples();
+        let channels = buffer.as_slice_immutable();
+        if !self.check_channel_lengths(channels, sample_count) {
+            return Vec::new();
+        }
+        if self.channels.len() < channels.len() {
+            self.channels.resize_with(channels.len(), ChannelState::default);
+        }
+
+        let mut spectra = Vec::with_capacity(channels.len());
+        for (channel, samples) in channels.iter().enumerate() {
+            let config = *self.channel_config(channel);
+            let state = &mut self.channels[channel];
+            state.prepare(&config, self.sample_rate, &mut self.fft_backend);
+            state.transform(&samples[..sample_count]);
+            mirror_spectrum(&mut state.scratch);
+            spectra.push(state.scratch.clone());
+        }
+
+        spectra
+    }
+
+    /// Get the phase correlation of a stereo buffer, for a correlation meter. This is the
+    /// normalized cross-correlation of the left and right samples at zero lag,
+    /// `Σ l·r / √(Σ l² · Σ r²)`, which is `1.0` for identical channels, `-1.0` when one channel
+    /// is the other inverted, and around `0.0` for unrelated channels. A buffer where either
+    /// channel is silent has no phase relationship and reads `0.0`.
+    ///
+    /// Returns `None` if the buffer doesn't have exactly two channels, or if they are not both
+    /// [`Buffer::samples()`] long.
+    pub fn phase_correlation(&mut self, buffer: &mut Buffer) -> Option<f32> {
+        let sample_count = buffer.samples();
+        let [left, right] = buffer.as_slice_immutable() else {
+            return None;
+        };
+        if !self.check_channel_lengths(&[&**left, &**right], sample_count) {
+            return None;
+        }
+
+        let (mut product, mut left_power, mut right_power) = (0.0, 0.0, 0.0);
+        for (l, r) in left.iter().zip(right.iter()) {
+            product += l * r;
+            left_power += l * l;
+            right_power += r * r;
+        }
+
+        let norm = (left_power * right_power).sqrt();
+        Some(if norm > 0.0 { (product / norm).clamp(-1.0, 1.0) } else { 0.0 })
+    }
+
+    /// Measure the frequency response from a `reference` signal, such as the sidechain input, to
+    /// the `main` signal, for instance a room or an EQ the reference is played through. Every
+    /// call adds the most recent frame of both buffers to averaged auto- and cross-spectra, from
+    /// which the transfer function `H(f) = Sxy / Sxx` and the coherence are computed, so the
+    /// measurement gets more reliable the longer it runs. Channels are paired up, and all pairs
+    /// are averaged together. The frames are windowed like in [`Analyzer::process()`] with the
+    /// configuration of the first channel.
+    ///
+    /// The average starts over when the FFT size changes and on [`Analyzer::reset()`]. If the
+    /// channels of either buffer are not all [`Buffer::samples()`] long, nothing is added to
+    /// the average.
+    pub fn transfer_function(
+        &mut self,
+        main: &mut Buffer,
+        reference: &mut Buffer,
+    ) -> TransferResult {
+        let (main_samples, reference_samples) = (main.samples(), reference.samples());
+        let main_channels = main.as_slice_immutable();
+        let reference_channels = reference.as_slice_immutable();
+        let config = *self.channel_config(0);
+        if self.transfer.as_ref().map(TransferAverage::fft_size) != Some(config.fft_size) {
+            self.transfer = Some(TransferAverage::new(config.fft_size));
+        }
+
+        if self.check_channel_lengths(main_channels, main_samples)
+            && self.check_channel_lengths(reference_channels, reference_samples)
+        {
+            if self.channels.is_empty() {
+                self.channels.push(ChannelState::default());
+            }
+            let state = &mut self.channels[0];
+            state.prepare(&config, self.sample_rate, &mut self.fft_backend);
+            if let Some(average) = &mut self.transfer {
+                for (main, reference) in main_channels.iter().zip(reference_channels.iter()) {
+                    state.transform(&reference[..reference_samples]);
+                    average.set_reference(&state.scratch);
+                    state.transform(&main[..main_samples]);
+                    average.accumulate(&state.scratch);
+                }
+            }
+        }
+
+        self.transfer
+            .as_ref()
+            .map_or_else(TransferResult::default, |average| average.result(self.sample_rate))
+    }
+
+    /// Queue samples of a channel for analysis, without going through a [`Buffer`]. This makes
+    /// it possible to use the analyzer outside of a plugin, for instance on samples read from a
+    /// file. The samples are analyzed by [`Analyzer::poll()`]. If this overflows the channel's
+    /// queue, its oldest samples are discarded, see [`Analyzer::set_pending_capacity()`].
+    pub fn push_samples(&mut self, channel: usize, samples: &[f32]) {
+        if self.pending.len() <= channel {
+            self.pending.resize_with(channel + 1, VecDeque::new);
+        }
+
+        let queue = &mut self.pending[channel];
+        queue.extend(samples);
+        let excess =
+            self.pending_capacity.map_or(0, |capacity| queue.len().saturating_sub(capacity));
+        if excess > 0 {
+            queue.drain(..excess);
+            self.discard(channel, excess);
+        }
+    }
+
+    /// Get the most samples the queue of every channel holds, or `None` if the queues grow
+    /// indefinitely.
+    pub fn pending_capacity(&self) -> Option<usize> {
+        self.pending_capacity
+    }
+
+    /// Limit the queues of [`Analyzer::push_samples()`] to `capacity` samples per channel. When
+    /// pushing overflows a queue, its oldest samples are discarded, and the whole frames lost with
+    /// them are counted by [`Analyzer::frames_dropped()`]. This bounds the memory of a consumer
+    /// that pushes faster than it polls. A capacity below the frame size means
+    /// [`Analyzer::poll()`] never gets a whole frame. Queues that are already longer are trimmed
+    /// the next time samples are pushed to them. Defaults to `None`, which lets the queues grow
+    /// indefinitely.
+    pub fn set_pending_capacity(&mut self, capacity: Option<usize>) {
+        self.pending_capacity = capacity;
+    }
+
+    /// Analyze the next frame of the samples queued with [`Analyzer::push_samples()`], or return
+    /// `None` if not every channel has a whole frame queued yet. A frame is as long as the
+    /// largest FFT size of the channels, and frames overlap by [`Analyzer::overlap()`]. The
+    /// samples that no later frame needs are removed from the queues, so call this in a loop
+    /// until it returns `None` to analyze everything that has been pushed. Queued samples are
+    /// kept until they are analyzed, so pushing without polling grows the queues indefinitely
+    /// unless they have a capacity, see [`Analyzer::set_pending_capacity()`].
+    pub fn poll(&mut self) -> Option<Vec<AnalyzerResult>> {
+        let frame_size = (0..self.pending.len())
+            .map(|channel| self.channel_config(channel).fft_size)
+            .max()?;
+        if self.pending.iter().any(|queue| queue.len() < frame_size) {
+            return None;
+        }
+
+        let mut pending = std::mem::take(&mut self.pending);
+        let channels = pending
+            .iter_mut()
+            .map(|queue| &queue.make_contiguous()[..frame_size])
+            .collect::<Vec<_>>();
+        let hop_size = self.hop_size(frame_size);
+        self.analyze_block(&channels, frame_size, hop_size);
+        for queue in &mut pending {
+            queue.drain(..hop_size);
+        }
+        self.pending = pending;
+
+        Some(self.results.clone())
+    }
+
+    /// Analyze the samples that are left in the queues of [`Analyzer::push_samples()`] after
+    /// polling, which are less than a whole frame, as a final frame padded with zeros. This is
+    /// what keeps the end of an offline render from going unanalyzed. The queues are empty
+    /// afterwards. Returns nothing if no samples are queued.
+    pub fn flush(&mut self) -> Vec<AnalyzerResult> {
+        let sample_count = self.pending.iter().map(VecDeque::len).max().unwrap_or(0);
+        if sample_count == 0 {
+            return Vec::new();
+        }
+
+        let mut pending = std::mem::take(&mut self.pending);
+        for queue in &mut pending {
+            queue.resize(sample_count, 0.0);
+        }
+        let channels =
+            pending.iter_mut().map(|queue| &*queue.make_contiguous()).collect::<Vec<_>>();
+        self.analyze_block(&channels, sample_count, self.hop_size(self.profile_config.fft_size));
+        for queue in &mut pending {
+            queue.clear();
+        }
+        self.pending = pending;
+
+        self.results.clone()
+    }
+
+    /// Analyze a whole signal frame by frame, calling `f` with the results of every frame. The
+    /// first frame ends after one FFT size worth of samples, or at the end of the signal if it is
+    /// shorter than that, and every next frame ends `hop_size` samples later. The results passed
+    /// to `f` are the same reused buffers [`Analyzer::process()`] returns, so only one frame is
+    /// held in memory at a time no matter how long the signal is. Collect them in `f` if all
+    /// frames are needed. Like [`Analyzer::process()`], nothing is analyzed if the channels don't
+    /// all have the same length.
+    pub fn process_samples_streaming(
+        &mut self,
+        channels: &[&[f32]],
+        hop_size: usize,
+        mut f: impl FnMut(&[AnalyzerResult]),
+    ) {
+        let sample_count = channels.first().map_or(0, |channel| channel.len());
+        if !self.check_channel_lengths(channels, sample_count) {
+            return;
+        }
+
+        let mut end = self.profile_config.fft_size.min(sample_count);
+        loop {
+            self.analyze_block(channels, end, hop_size.max(1));
+            f(&self.results);
+
+            end += hop_size.max(1);
+            if end > sample_count {
+                break;
+            }
+        }
+    }
+
+    /// Estimate the power spectral density of every channel with Welch's method. Each channel is
+    /// split into segments of `segment_len` samples that overlap by the fraction `overlap`, every
+    /// segment is windowed with the configured window and transformed, and the periodograms of
+    /// all segments are averaged. The result is normalized by the power of the window and the
+    /// sample rate, so white noise with a variance of `σ²` has a flat density of `2σ² / fs`.
+    ///
+    /// The segment length trades variance for resolution: longer segments resolve finer details
+    /// but leave fewer segments to average, which makes the estimate noisier. Overlapping the
+    /// segments by half with a Hann window recovers most of the variance lost to windowing.
+    /// A channel that is shorter than one segment is analyzed as a single zero padded segment.
+    /// This allocates, so it's meant for measurements rather than the realtime path.
+    pub fn process_psd(
+        &mut self,
+        buffer: &mut Buffer,
+        segment_len: usize,
+        overlap: f32,
+    ) -> Vec<PsdResult> {
+        let segment_len = segment_len.max(2);
+        let hop_size = ((segment_len as f32 * (1.0 - overlap.clamp(0.0, 0.99))) as usize).max(1);
+        let bin_count = self.bin_count(segment_len);
+        let mut fft = self.fft_backend.plan(segment_len);
+        let mut window = Vec::new();
+        self.profile_config.window.fill(&mut window, segment_len);
+        let window_power = window.iter().map(|coefficient| coefficient * coefficient).sum::<f32>();
+
+        let bin_width = self.sample_rate / segment_len as f32;
+        let frequencies = (0..bin_count).map(|i| i as f32 * bin_width).collect::<Vec<_>>();
+        let mut complex_samples = vec![Complex::new(0.0, 0.0); segment_len];
+        buffer
+            .as_slice_immutable()
+            .iter()
+            .map(|samples| {
+                let mut power = vec![0.0; bin_count];
+                let mut segment_count = 0;
+                let mut start = 0;
+                loop {
+                    let segment = &samples[start..(start + segment_len).min(samples.len())];
+                    for (i, bin) in complex_samples.iter_mut().enumerate() {
+                        let sample = segment.get(i).copied().unwrap_or(0.0);
+                        *bin = Complex::new(sample * window[i], 0.0);
+                    }
+
+                    fft.process(&mut complex_samples);
+                    for (power, bin) in power.iter_mut().zip(&complex_samples) {
+                        *power += bin.norm_sqr();
+                    }
+                    segment_count += 1;
+
+                    start += hop_size;
+                    if start + segment_len > samples.len() {
+                        break;
+                    }
+                }
+
+                // The negative frequencies are folded onto the positive ones, except for the DC
+                // and Nyquist bins which only exist once.
+                let scale = 1.0 / (segment_count as f32 * window_power * self.sample_rate);
+                for (bin, power) in power.iter_mut().enumerate() {
+                    let one_sided = if bin == 0 || bin * 2 == segment_len { 1.0 } else { 2.0 };
+                    *power *= one_sided * scale;
+                }
+
+                PsdResult { frequencies: frequencies.clone(), power }
+            })
+            .collect()
+    }
+
+    /// Compute the constant-Q transform of every channel, with `bins_per_octave` geometrically
+    /// spaced bins per octave from `f_min` up to the Nyquist frequency. Unlike the FFT's linearly
+    /// spaced bins, every bin spans the same musical interval, which resolves bass notes as well
+    /// as treble notes. The lowest bin needs the longest frame, so each channel is analyzed by
+    /// its most recent `Q * sample_rate / f_min` samples rounded up to a power of two, where
+    /// `Q = 1 / (2^(1 / bins_per_octave) - 1)`, and is padded with zeros if it's shorter. The
+    /// transform kernel is computed on the first call and whenever the parameters or the sample
+    /// rate change.
+    pub fn process_cqt(
+        &mut self,
+        buffer: &mut Buffer,
+        bins_per_octave: u32,
+        f_min: f32,
+    ) -> Vec<CqtResult> {
+        let sample_rate = self.sample_rate;
+        if !self
+            .cqt_kernel
+            .as_ref()
+            .is_some_and(|kernel| kernel.matches(sample_rate, bins_per_octave, f_min))
+        {
+            self.cqt_kernel = Some(CqtKernel::new(
+                &mut self.fft_planner,
+                sample_rate,
+                bins_per_octave,
+                f_min,
+            ));
+        }
+
+        let Some(kernel) = &self.cqt_kernel else {
+            return Vec::new();
+        };
+        let mut scratch = Vec::new();
+        buffer
+            .as_slice_immutable()
+            .iter()
+            .map(|samples| kernel.transform(samples, &mut scratch))
+            .collect()
+    }
+
+    /// Get the kernel [`Analyzer::process_cqt()`] last used, if any.
+    pub fn cqt_kernel(&self) -> Option<&CqtKernel> {
+        self.cqt_kernel.as_ref()
+    }
+
+    /// Replace the constant-Q transform kernel with one computed elsewhere, such as on a
+    /// background thread, so [`Analyzer::process_cqt()`] doesn't have to compute it when it's
+    /// called with the kernel's parameters.
+    pub fn set_cqt_kernel(&mut self, kernel: CqtKernel) {
+        self.cqt_kernel = Some(kernel);
+    }
+
+    /// Process the buffer and apply a filterbank of `num_mels` triangular filters to the power
+    /// spectrum of every channel. The filters are spaced evenly on the mel scale between `f_min`
+    /// and `f_max`, using `mel = 2595 * log10(1 + f / 700)` (see [`crate::mel::hz_to_mel()`]),
+    /// and every filter peaks at a weight of one at its center frequency. The filterbank is
+    /// computed on the first call and whenever the parameters, the sample rate or the FFT size
+    /// change.
+    pub fn process_mel(
+        &mut self,
+        buffer: &mut Buffer,
+        num_mels: usize,
+        f_min: f32,
+        f_max: f32,
+    ) -> Vec<MelResult> {
+        let result_count = self.process(buffer).len();
+        let mut mel_results = Vec::with_capacity(result_count);
+        for result in &self.results[..result_count] {
+            if result.frequencies.len() < 2 {
+                mel_results.push(MelResult::default());
+                continue;
+            }
+
+            let bin_width = result.frequencies[1] - result.frequencies[0];
+            let bin_count = result.magnitudes.len();
+            let filterbank = match &mut self.mel_filterbank {
+                Some(filterbank)
+                    if filterbank.matches(bin_width, bin_count, num_mels, f_min, f_max) =>
+                {
+                    filterbank
+                }
+                filterbank => filterbank
+                    .insert(MelFilterbank::new(bin_width, bin_count, num_mels, f_min, f_max)),
+            };
+            mel_results.push(filterbank.apply(&result.magnitudes));
+        }
+
+        mel_results
+    }
+
+    /// Process the buffer and analyze the spectrum, returning only the magnitudes in dB for every
+    /// channel. The frequency of each bin can be found in [`Analyzer::frequencies()`]. No level
+    /// is below [`Analyzer::floor_db()`]. Powers are converted with `10 * log10`, so both
+    /// magnitude scales give the same levels.
+    pub fn process_db(&mut self, buffer: &mut Buffer) -> Vec<Vec<f32>> {
+        let floor_db = self.floor_db;
+        let to_db = match self.magnitude_scale {
+            MagnitudeScale::Linear => amplitude_to_db,
+            MagnitudeScale::Power => power_to_db,
+        };
+        self.process(buffer)
+            .iter()
+            .map(|result| {
+                result.magnitudes.iter().map(|&magnitude| to_db(magnitude, floor_db)).collect()
+            })
+            .collect()
+    }
+
+    /// Analyze a block of samples of a single channel with the channel's configuration, and write
+    /// the result into the channel's entry in [`Self::results`].
+    fn analyze_channel(&mut self, channel: usize, samples: &[f32]) {
+        let config = *self.channel_config(channel);
+        let prefilter = self.prefilter_coefficient();
+        let state = &mut self.channels[channel];
+        state.prepare(&config, self.sample_rate, &mut self.fft_backend);
+
+        let settings = FrameSettings {
+            sample_rate: self.sample_rate,
+            include_nyquist: self.include_nyquist,
+            phase_output: self.phase_output,
+            phase_smoothing: self.phase_smoothing,
+            smoothing: self.smoothing,
+            slope_db_per_octave: self.slope_db_per_octave,
+            slope_reference: self.slope_reference,
+            magnitude_scale: self.magnitude_scale,
+            smart_crossover: self.smart_crossover,
+            prefilter,
+            decimation_filter: self.multiresolution.then_some(&self.decimation_filter[..]),
+            amplitude_correction: self.amplitude_correction,
+            global_fft_size: self.profile_config.fft_size,
+            frequencies: &self.frequencies,
+        };
+        state.analyze(samples, &settings, &mut self.results[channel]);
+    }
+
+    /// Check that every channel is `sample_count` samples long, logging the first mismatch.
+    fn check_channel_lengths(
+        &mut self,
+        channels: &[impl AsRef<[f32]>],
+        sample_count: usize,
+    ) -> bool {
+        if channels.iter().all(|channel| channel.as_ref().len() == sample_count) {
+            return true;
+        }
+
+        if !self.reported_length_mismatch {
+            nih_warn!("Skipping channels that are not all {sample_count} samples long");
+            self.reported_length_mismatch = true;
+        }
+
+        false
+    }
+
+    /// Analyze the first `sample_count` samples of every channel into [`Self::results`], as a
+    /// frame that starts `interval` samples after the previous one. The results are left as they
+    /// are while the analyzer is frozen.
+    fn analyze_block(
+        &mut self,
+        channels: &[impl AsRef<[f32]> + Sync],
+        sample_count: usize,
+        interval: usize,
+    ) {
+        if self.frozen {
+            return;
+        }
+        if interval != self.smoothing_interval {
+            self.update_smoothing_for(interval);
+        }
+        self.frames_processed += 1;
+
+        if self.is_silent(channels, sample_count) {
+            for (state, result) in self.channels.iter_mut().zip(&mut self.results) {
+                state.decay(&mut result.magnitudes, self.smoothing);
+                (result.rms, result.peak) = (0.0, 0.0);
+            }
+            // The results may be limited to a frequency range, so the average of all bins is
+            // decayed the same way instead of being recomputed from them.
+            for average in &mut self.average {
+                *average *= self.smoothing;
+            }
+            self.silent_blocks += 1;
+            return;
+        }
+        self.silent_blocks = 0;
+
+        let channel_count = channels.len();
+        if self.channels.len() < channel_count {
+            self.channels.resize_with(channel_count, ChannelState::default);
+        }
+
+        if self.channel_mode == ChannelMode::MidSide && channel_count == 2 {
+            let left = &channels[0].as_ref()[..sample_count];
+            let right = &channels[1].as_ref()[..sample_count];
+            let mut mixed = std::mem::take(&mut self.mixed);
+            mixed.resize_with(2, Vec::new);
+            mixed[0].clear();
+            mixed[0].extend(left.iter().zip(right.iter()).map(|(l, r)| (l + r) / 2.0));
+            mixed[1].clear();
+            mixed[1].extend(left.iter().zip(right.iter()).map(|(l, r)| (l - r) / 2.0));
+
+            self.results.resize_with(2, AnalyzerResult::default);
+            for (channel, label) in ["Mid", "Side"].into_iter().enumerate() {
+                self.analyze_channel(channel, &mixed[channel]);
+                let result = &mut self.results[channel];
+                if result.label.as_deref() != Some(label) {
+                    result.label = Some(String::from(label));
+                }
+            }
+            self.mixed = mixed;
+        } else if self.channel_mode == ChannelMode::Sum && channel_count > 0 {
+            let scale = 1.0 / channel_count as f32;
+            let mut mixed = std::mem::take(&mut self.mixed);
+            mixed.resize_with(mixed.len().max(1), Vec::new);
+            mixed[0].clear();
+            mixed[0].resize(sample_count, 0.0);
+            for channel in channels {
+                for (sum, sample) in mixed[0].iter_mut().zip(&channel.as_ref()[..sample_count]) {
+                    *sum += sample * scale;
+                }
+            }
+
+            self.results.resize_with(1, AnalyzerResult::default);
+            self.analyze_channel(0, &mixed[0]);
+            let result = &mut self.results[0];
+            if result.label.as_deref() != Some("Sum") {
+                result.label = Some(String::from("Sum"));
+            }
+            self.mixed = mixed;
+        } else {
+            self.results.resize_with(channel_count, AnalyzerResult::default);
+            self.analyze_discrete(channels, sample_count);
+        }
+
+        for (channel, result) in self.results.iter_mut().enumerate() {
+            result.channel = channel;
+        }
+        self.update_average();
+        self.limit_to_frequency_range();
+    }
+
+    /// Remove the bins outside of [`Self::frequency_range`] from every result.
+    fn limit_to_frequency_range(&mut self) {
+        let Some((low, high)) = self.frequency_range else {
+            for result in &mut self.results {
+                result.start_bin = 0;
+            }
+            return;
+        };
+
+        for result in &mut self.results {
+            let start = result.frequencies.partition_point(|&frequency| frequency < low);
+            let end = result.frequencies.partition_point(|&frequency| frequency <= high);
+            for bins in [&mut result.frequencies, &mut result.magnitudes]
+                .into_iter()
+                .chain(result.phases.as_mut())
+            {
+                bins.truncate(end);
+                bins.drain(..start.min(end));
+            }
+            result.start_bin = start;
+        }
+    }
+
+    /// Get the number of results the channel mode turns `channel_count` channels into.
+    fn result_count(&self, channel_count: usize) -> usize {
+        match self.channel_mode {
+            ChannelMode::MidSide | ChannelMode::Discrete => channel_count,
+            ChannelMode::Sum => channel_count.min(1),
+        }
+    }
+
+    /// Whether the first `sample_count` samples of every channel stay below the silence
+    /// threshold, and there are results for all of them to decay instead of analyzing them.
+    fn is_silent(&self, channels: &[impl AsRef<[f32]>], sample_count: usize) -> bool {
+        let threshold = 10.0_f32.powf(self.silence_threshold_db / 20.0);
+        self.results.len() == self.result_count(channels.len())
+            && self.results.iter().all(|result| !result.magnitudes.is_empty())
+            && channels.iter().all(|channel| {
+                channel.as_ref()[..sample_count].iter().all(|sample| sample.abs() < threshold)
+            })
+    }
+
+    /// Analyze the first `sample_count` samples of every channel on its own.
+    #[cfg(not(feature = "rayon"))]
+    fn analyze_discrete(&mut self, channels: &[impl AsRef<[f32]> + Sync], sample_count: usize) {
+        for (channel, channel_samples) in channels.iter().enumerate() {
+            self.analyze_channel(channel, &channel_samples.as_ref()[..sample_count]);
+            self.results[channel].label = None;
+        }
+    }
+
+    /// Analyze the first `sample_count` samples of every channel on its own. The channels are
+    /// independent, so after preparing them on this thread they are analyzed in parallel on
+    /// rayon's thread pool.
+    #[cfg(feature = "rayon")]
+    fn analyze_discrete(&mut self, channels: &[impl AsRef<[f32]> + Sync], sample_count: usize) {
+        use rayon::prelude::*;
+
+        for channel in 0..channels.len() {
+            let config = *self.channel_config(channel);
+            self.channels[channel].prepare(&config, self.sample_rate, &mut self.fft_backend);
+        }
+
+        let settings = FrameSettings {
+            sample_rate: self.sample_rate,
+            include_nyquist: self.include_nyquist,
+            phase_output: self.phase_output,
+            phase_smoothing: self.phase_smoothing,
+            smoothing: self.smoothing,
+            slope_db_per_octave: self.slope_db_per_octave,
+            slope_reference: self.slope_reference,
+            magnitude_scale: self.magnitude_scale,
+            smart_crossover: self.smart_crossover,
+            prefilter: self.prefilter_coefficient(),
+            decimation_filter: self.multiresolution.then_some(&self.decimation_filter[..]),
+            amplitude_correction: self.amplitude_correction,
+            global_fft_size: self.profile_config.fft_size,
+            frequencies: &self.frequencies,
+        };
+        self.channels[..channels.len()]
+            .par_iter_mut()
+            .zip(self.results.par_iter_mut())
+            .zip(channels.par_iter())
+            .for_each(|((state, result), channel_samples)| {
+                state.analyze(&channel_samples.as_ref()[..sample_count], &settings, result);
+                result.label = None;
+            });
+    }
+
+    /// Get the coefficient of the high-pass pre-filter for the current sample rate, or `None` if
+    /// the filter is disabled.
+    fn prefilter_coefficient(&self) -> Option<f32> {
+        (self.prefilter_cutoff > 0.0).then(|| {
+            (-2.0 * std::f32::consts::PI * self.prefilter_cutoff / self.sample_rate).exp()
+        })
+    }
+
+    /// Count the `excess` samples the queue of `channel` just discarded. The frames are shared by
+    /// all channels, so the whole hops the channel has discarded in total are only counted as
+    /// dropped frames as far as they exceed what the other channels have discarded.
+    fn discard(&mut self, channel: usize, excess: usize) {
+        let frame_size = (0..self.pending.len())
+            .map(|channel| self.channel_config(channel).fft_size)
+            .max()
+            .unwrap_or(self.profile_config.fft_size);
+        let hop_size = self.hop_size(frame_size) as u64;
+        if self.discarded.len() <= channel {
+            self.discarded.resize(channel + 1, 0);
+        }
+
+        let before = self.discarded.iter().copied().max().unwrap_or(0);
+        self.discarded[channel] += excess as u64;
+        let after = before.max(self.discarded[channel]);
+        self.frames_dropped += after / hop_size - before / hop_size;
+    }
+
+    /// Get the number of samples between the starts of two consecutive frames of `frame_size`
+    /// samples, as determined by the overlap of the current profile.
+    fn hop_size(&self, frame_size: usize) -> usize {
+        let overlap =
+            if self.offline { self.overlap.max(OFFLINE_MIN_OVERLAP) } else { self.overlap };
+        ((frame_size as f32 * (1.0 - overlap)) as usize).clamp(1, frame_size)
+    }
+
+    /// Derive the smoothing coefficient from the time constant if the smoothing was set as a
+    /// time, for the frames of [`Analyzer::poll()`] at the current sample rate, FFT size and
+    /// overlap.
+    fn update_smoothing(&mut self) {
+        self.update_smoothing_for(self.hop_size(self.profile_config.fft_size));
+    }
+
+    /// Derive the smoothing coefficient from the time constant if the smoothing was set as a
+    /// time, for frames that start `interval` samples apart at the current sample rate.
+    fn update_smoothing_for(&mut self, interval: usize) {
+        if let Some(time) = self.smoothing_time {
+            let interval_time = interval as f32 / self.sample_rate;
+            self.smoothing = if time > 0.0 { (-interval_time / time).exp() } else { 0.0 };
+            self.smoothing_interval = interval;
+        }
+    }
+
+    /// Check that a frequency range is increasing and within zero and the Nyquist frequency.
+    fn check_frequency_range(&self, low: f32, high: f32) -> Result<(), ConfigError> {
+        if 0.0 <= low && low < high && high <= self.sample_rate / 2.0 {
+            Ok(())
+        } else {
+            Err(ConfigError::InvalidFrequencyRange(low, high))
+        }
+    }
+
+    /// Get the number of bins in a result for the given FFT size.
+    fn bin_count(&self, fft_size: usize) -> usize {
+        if self.include_nyquist { fft_size / 2 + 1 } else { fft_size / 2 }
+    }
+
+    /// Get the FFT size that is analyzed for the given configured FFT size in the current profile.
+    fn profile_fft_size(&self, fft_size: usize) -> usize {
+        if self.offline { (fft_size * OFFLINE_FFT_SIZE_FACTOR).min(MAX_FFT_SIZE) } else { fft_size }
+    }
+
+    /// Recompute the center frequencies of the bins for the global configuration.
+    fn update_frequencies(&mut self) {
+        let fft_size = self.profile_config.fft_size;
+        let bin_width = self.sample_rate / fft_size as f32;
+        let bin_count = self.bin_count(fft_size);
+        self.frequencies.clear();
+        self.frequencies.extend((0..bin_count).map(|i| i as f32 * bin_width));
+    }
+
+    /// Average the magnitudes of all channels that use the global FFT size into
+    /// [`Self::average`].
+    fn update_average(&mut self) {
+        let fft_size = self.profile_config.fft_size;
+        let bin_count = self.bin_count(fft_size);
+        self.average.clear();
+        self.average.resize(bin_count, 0.0);
+        self.average_bin_width = self.sample_rate / fft_size as f32;
+
+        let matching = self.results.iter().filter(|result| result.magnitudes.len() == bin_count);
+        let count = matching.clone().count();
+        if count == 0 {
+            self.average.clear();
+            return;
+        }
+
+        let scale = 1.0 / count as f32;
+        for result in matching {
+            for (average, magnitude) in self.average.iter_mut().zip(&result.magnitudes) {
+                *average += magnitude * scale;
+            }
+        }
+    }
+}
+
+/// Make sure `vec` can hold `capacity` elements without reallocating.
+fn reserve_total<T>(vec: &mut Vec<T>, capacity: usize) {
+    vec.reserve(capacity.saturating_sub(vec.len()));
+}
+
+/// Get the most bins a result can have for the given FFT size, which is when both the Nyquist bin
+/// and the bins of the multiresolution analysis are included.
+fn result_capacity(fft_size: usize) -> usize {
+    fft_size / 2 + 1 + fft_size / MULTIRESOLUTION_DECIMATION
+}
+
+/// Get the linear gain that tilts a bin at `frequency` by `slope` dB per octave, relative to the
+/// `reference` frequency. The DC bin has no octave distance to the reference, so it's left as is.
+fn slope_gain(frequency: f32, slope: f32, reference: f32) -> f32 {
+    if frequency > 0.0 { 10.0_f32.powf(slope * (frequency / reference).log2() / 20.0) } else { 1.0 }
+}
+
+/// Unwrap a phase spectrum in place, by adding multiples of 2π so that the difference between two
+/// neighbouring bins never exceeds π.
+fn unwrap_phase(phases: &mut [f32]) {
+    use std::f32::consts::{PI, TAU};
+
+    let mut offset = 0.0;
+    let mut previous = match phases.first() {
+        Some(&phase) => phase,
+        None => return,
+    };
+    for phase in phases.iter_mut().skip(1) {
+        let wrapped = *phase;
+        let delta = wrapped - previous;
+        if delta > PI {
+            offset -= TAU * ((delta + PI) / TAU).floor();
+        } else if delta < -PI {
+            offset += TAU * ((-delta + PI) / TAU).floor();
+        }
+
+        previous = wrapped;
+        *phase = wrapped + offset;
+    }
+}
+
+/// Smooth a spectrum in place by replacing every bin with the mean of the bins within a window of
+/// the given width in octaves centered around it. Because the bins are spaced linearly, the window
+/// covers more bins at higher frequencies. The DC bin is left as is.
+fn smooth_fractional_octave(values: &mut [f32], octaves: f32) {
+    // A running sum makes the mean of any range of bins a single subtraction.
+    let mut prefix_sums = Vec::with_capacity(values.len() + 1);
+    prefix_sums.push(0.0);
+    for value in values.iter() {
+        prefix_sums.push(prefix_sums[prefix_sums.len() - 1] + value);
+    }
+
+    let half_width = 2.0_f32.powf(octaves / 2.0);
+    let last = values.len().saturating_sub(1);
+    for (bin, value) in values.iter_mut().enumerate().skip(1) {
+        let low = ((bin as f32 / half_width).floor() as usize).max(1);
+        let high = ((bin as f32 * half_width).ceil() as usize).min(last);
+        *value = (prefix_sums[high + 1] - prefix_sums[low]) / (high + 1 - low) as f32;
+    }
+}
+
+/// Blend the magnitudes of the small FFT of the smart resolution mode into the magnitudes of the
+/// large FFT. Below an octave around the crossover the large FFT is used, above it the small FFT
+/// is used, and in between the two are crossfaded on a logarithmic frequency scale.
+fn blend_smart_resolution(
+    magnitudes: &mut [f32],
+    small_bins: &[Complex<f32>],
+    bin_width: f32,
+    crossover: f32,
+) {
+    let low = crossover / std::f32::consts::SQRT_2;
+    let high = crossover * std::f32::consts::SQRT_2;
+    let last = small_bins.len().saturating_sub(1);
+    let scale = SMART_RESOLUTION_RATIO as f32;
+    for (bin, magnitude) in magnitudes.iter_mut().enumerate() {
+        let frequency = bin as f32 * bin_width;
+        if frequency <= low {
+            continue;
+        }
+
+        // The small FFT's bins are `SMART_RESOLUTION_RATIO` times as wide, so its magnitude at
+        // this bin's frequency is interpolated from its two nearest bins.
+        let position = bin as f32 / scale;
+        let below = (position.floor() as usize).min(last);
+        let above = (below + 1).min(last);
+        let fraction = position - below as f32;
+        let small_magnitude = scale
+            * (small_bins[below].norm() * (1.0 - fraction) + small_bins[above].norm() * fraction);
+
+        let weight = if frequency >= high { 1.0 } else { (frequency / low).log2() };
+        *magnitude += (small_magnitude - *magnitude) * weight;
+    }
+}
+
+/// Convert an amplitude to dB, raising levels below `floor_db` to the floor. Amplitudes of zero
+/// are at the floor rather than negative infinity.
+pub(crate) fn amplitude_to_db(magnitude: f32, floor_db: f32) -> f32 {
+    (20.0 * magnitude.log10()).max(floor_db)
+}
+
+/// Convert a power to dB, like [`amplitude_to_db()`] does for amplitudes.
+fn power_to_db(power: f32, floor_db: f32) -> f32 {
+    (10.0 * power.log10()).max(floor_db)
+}
+
+/// Normalize the magnitudes of a frame of `fft_size` samples so they depend on neither the FFT
+/// size nor the window. The energy of the negative frequencies is folded onto the positive ones,
+/// except for the DC and Nyquist bins which only exist once.
+fn correct_amplitudes(magnitudes: &mut [f32], fft_size: usize, coherent_gain: f32) {
+    let scale = 1.0 / (fft_size as f32 * coherent_gain);
+    for (bin, magnitude) in magnitudes.iter_mut().enumerate() {
+        let one_sided = if bin == 0 || bin * 2 == fft_size { 1.0 } else { 2.0 };
+        *magnitude *= one_sided * scale;
+    }
+}
+
+/// Design the anti-aliasing filter for the decimated analysis of the multiresolution mode. This
+/// is a Blackman windowed sinc filter with its cutoff at the Nyquist frequency of the decimated
+/// signal, normalized to unity gain at DC.
+fn decimation_filter() -> Vec<f32> {
+    let cutoff = 0.5 / MULTIRESOLUTION_DECIMATION as f32;
+    let center = (DECIMATION_FILTER_TAPS / 2) as f32;
+    let mut filter = (0..DECIMATION_FILTER_TAPS)
+        .map(|tap| {
+            let offset = tap as f32 - center;
+            let sinc = if offset == 0.0 {
+                1.0
+            } else {
+                (2.0 * std::f32::consts::PI * cutoff * offset).sin()
+                    / (2.0 * std::f32::consts::PI * cutoff * offset)
+            };
+            sinc * WindowFunction::Blackman.coefficient(tap, DECIMATION_FILTER_TAPS - 1)
+        })
+        .collect::<Vec<_>>();
+    let sum = filter.iter().sum::<f32>();
+    for coefficient in &mut filter {
+        *coefficient /= sum;
+    }
+
+    filter
+}
@@ -0,0 +1,105 @@
+use rustfft::FftPlanner;
+use rustfft::num_complex::Complex;
+use crate::analyzer::AnalyzerResult;
+use crate::config::{AnalyzerConfig, ConfigError};
+use crate::weighting::WeightingCurve;
+
+/// A double precision version of the [`Analyzer`](crate::analyzer::Analyzer) for offline use,
+/// where very long frames make the rounding errors of a single precision FFT noticeable. The
+/// window, the FFT and the magnitudes are all computed in `f64`, and the magnitudes are only
+/// converted to `f32` when they are stored in the [`AnalyzerResult`]. This allocates on every
+/// call, so it's not meant for the realtime path.
+pub struct AnalyzerF64 {
+    fft_planner: FftPlanner<f64>,
+    sample_rate: f32,
+    config: AnalyzerConfig,
+}
+
+impl AnalyzerF64 {
+    /// Create a new instance of [`AnalyzerF64`] with defaults.
+    pub fn new(sample_rate: f32) -> Self {
+        AnalyzerF64 {
+            fft_planner: FftPlanner::new(),
+            sample_rate,
+            config: AnalyzerConfig::default(),
+        }
+    }
+
+    /// Get the sample rate for the analyzer to use.
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Set the sample rate for the analyzer to use.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+    }
+
+    /// Get the configuration the channels are analyzed with.
+    pub fn config(&self) -> &AnalyzerConfig {
+        &self.config
+    }
+
+    /// Replace the configuration the channels are analyzed with. The configuration is validated
+    /// against the current sample rate first, and is not applied if it's invalid.
+    pub fn apply_config(&mut self, config: AnalyzerConfig) -> Result<(), ConfigError> {
+        config.validate(self.sample_rate)?;
+        self.config = config;
+
+        Ok(())
+    }
+
+    /// Analyze the spectrum of every channel. Like [`Analyzer::process()`], each channel is
+    /// analyzed by its most recent `fft_size` samples, and is padded with zeros if it's shorter.
+    ///
+    /// [`Analyzer::process()`]: crate::analyzer::Analyzer::process()
+    pub fn process(&mut self, channels: &[&[f32]]) -> Vec<AnalyzerResult> {
+        let fft_size = self.config.fft_size;
+        let fft = self.fft_planner.plan_fft_forward(fft_size);
+        let bin_width = self.sample_rate / fft_size as f32;
+        let frequencies = (0..fft_size / 2).map(|i| i as f32 * bin_width).collect::<Vec<_>>();
+
+        let mut complex_samples = vec![Complex::new(0.0, 0.0); fft_size];
+        channels
+            .iter()
+            .enumerate()
+            .map(|(channel, samples)| {
+                let frame = &samples[samples.len().saturating_sub(fft_size)..];
+                let (mut sum_of_squares, mut peak) = (0.0, 0.0_f64);
+                for (i, bin) in complex_samples.iter_mut().enumerate() {
+                    let sample = frame.get(i).copied().unwrap_or(0.0) as f64;
+                    sum_of_squares += sample * sample;
+                    peak = peak.max(sample.abs());
+                    let coefficient = self.config.window.coefficient_f64(i, fft_size);
+                    *bin = Complex::new(sample * coefficient, 0.0);
+                }
+
+                fft.process(&mut complex_samples);
+
+                let magnitudes = complex_samples[..fft_size / 2]
+                    .iter()
+                    .zip(&frequencies)
+                    .map(|(bin, &frequency)| {
+                        let magnitude = (bin.re.powi(2) + bin.im.powi(2)).sqrt();
+                        if self.config.weighting == WeightingCurve::None {
+                            magnitude as f32
+                        } else {
+                            (magnitude * self.config.weighting.gain(frequency) as f64) as f32
+                        }
+                    })
+                    .collect();
+
+                AnalyzerResult {
+                    frequencies: frequencies.clone(),
+                    magnitudes,
+                    label: None,
+                    channel,
+                    phases: None,
+                    start_bin: 0,
+                    rms: (sum_of_squares / frame.len().max(1) as f64).sqrt() as f32,
+                    peak: peak as f32,
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,65 @@
+use crate::analyzer::Analyzer;
+use crate::config::{AnalyzerConfig, ConfigError};
+use crate::weighting::WeightingCurve;
+use crate::window::WindowFunction;
+
+/// Collects the settings of an [`Analyzer`], so they can be checked together and applied at
+/// once instead of through a sequence of setters. Settings that aren't given keep the defaults
+/// of [`Analyzer::new()`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct AnalyzerBuilder {
+    config: AnalyzerConfig,
+    overlap: f32,
+    smoothing: f32,
+}
+
+impl AnalyzerBuilder {
+    /// Set the number of samples in a frame, which must be a power of two between
+    /// [`MIN_FFT_SIZE`](crate::config::MIN_FFT_SIZE) and
+    /// [`MAX_FFT_SIZE`](crate::config::MAX_FFT_SIZE).
+    pub fn fft_size(mut self, fft_size: usize) -> Self {
+        self.config.fft_size = fft_size;
+        self
+    }
+
+    /// Set the window function, see [`Analyzer::set_window()`].
+    pub fn window(mut self, window: WindowFunction) -> Self {
+        self.config.window = window;
+        self
+    }
+
+    /// Set the fraction of a frame that consecutive frames share, which must be at least `0.0`
+    /// and less than `1.0`. See [`Analyzer::set_overlap()`].
+    pub fn overlap(mut self, overlap: f32) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    /// Set the smoothing over time, see [`Analyzer::set_smoothing()`].
+    pub fn smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// Set the frequency weighting curve, see [`Analyzer::set_weighting()`].
+    pub fn weighting(mut self, weighting: WeightingCurve) -> Self {
+        self.config.weighting = weighting;
+        self
+    }
+
+    /// Create an [`Analyzer`] for the given sample rate with these settings, or get the first
+    /// setting that can't be used.
+    pub fn build(self, sample_rate: f32) -> Result<Analyzer, ConfigError> {
+        self.config.validate(sample_rate)?;
+        if !(0.0..1.0).contains(&self.overlap) {
+            return Err(ConfigError::InvalidOverlap(self.overlap));
+        }
+
+        let mut analyzer = Analyzer::new(sample_rate);
+        analyzer.apply_config(self.config)?;
+        analyzer.set_overlap(self.overlap);
+        analyzer.set_smoothing(self.smoothing);
+
+        Ok(analyzer)
+    }
+}
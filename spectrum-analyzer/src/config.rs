@@ -0,0 +1,97 @@
+use std::fmt;
+use crate::weighting::WeightingCurve;
+use crate::window::WindowFunction;
+
+/// A snapshot of the settings that determine how the [`Analyzer`](crate::analyzer::Analyzer)
+/// analyzes a channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnalyzerConfig {
+    /// The number of samples in a frame. Each block is analyzed by its most recent `fft_size`
+    /// samples, and blocks that are shorter than this are padded with zeros.
+    pub fft_size: usize,
+    /// The window function that is applied to a frame before it is transformed.
+    pub window: WindowFunction,
+    /// The frequency weighting curve that is applied to the magnitudes.
+    pub weighting: WeightingCurve,
+}
+
+/// The smallest FFT size a configuration may use.
+pub const MIN_FFT_SIZE: usize = 16;
+/// The largest FFT size a configuration may use.
+pub const MAX_FFT_SIZE: usize = 1 << 18;
+
+/// The reason an [`AnalyzerConfig`] can't be used, as returned by [`AnalyzerConfig::validate()`],
+/// or the reason another setting of the [`Analyzer`](crate::analyzer::Analyzer) was rejected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigError {
+    /// The sample rate is not a positive, finite number.
+    InvalidSampleRate(f32),
+    /// The FFT size is smaller than [`MIN_FFT_SIZE`].
+    FftSizeTooSmall(usize),
+    /// The FFT size is larger than [`MAX_FFT_SIZE`].
+    FftSizeTooLarge(usize),
+    /// The FFT size is not a power of two.
+    FftSizeNotPowerOfTwo(usize),
+    /// The overlap is not a fraction in `[0, 1)`.
+    InvalidOverlap(f32),
+    /// The frequency range isn't an increasing range between zero and the Nyquist frequency.
+    InvalidFrequencyRange(f32, f32),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::InvalidSampleRate(sample_rate) => {
+                write!(f, "the sample rate {sample_rate} is not a positive number")
+            }
+            ConfigError::FftSizeTooSmall(fft_size) => {
+                write!(f, "the FFT size {fft_size} is smaller than {MIN_FFT_SIZE}")
+            }
+            ConfigError::FftSizeTooLarge(fft_size) => {
+                write!(f, "the FFT size {fft_size} is larger than {MAX_FFT_SIZE}")
+            }
+            ConfigError::FftSizeNotPowerOfTwo(fft_size) => {
+                write!(f, "the FFT size {fft_size} is not a power of two")
+            }
+            ConfigError::InvalidOverlap(overlap) => {
+                write!(f, "the overlap {overlap} is not at least 0 and less than 1")
+            }
+            ConfigError::InvalidFrequencyRange(low, high) => {
+                write!(f, "the frequency range {low} Hz to {high} Hz is not within the spectrum")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl AnalyzerConfig {
+    /// Check whether this configuration can be used at the given sample rate.
+    pub fn validate(&self, sample_rate: f32) -> Result<(), ConfigError> {
+        if !(sample_rate.is_finite() && sample_rate > 0.0) {
+            return Err(ConfigError::InvalidSampleRate(sample_rate));
+        }
+        if self.fft_size < MIN_FFT_SIZE {
+            return Err(ConfigError::FftSizeTooSmall(self.fft_size));
+        }
+        if self.fft_size > MAX_FFT_SIZE {
+            return Err(ConfigError::FftSizeTooLarge(self.fft_size));
+        }
+        if !self.fft_size.is_power_of_two() {
+            return Err(ConfigError::FftSizeNotPowerOfTwo(self.fft_size));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for AnalyzerConfig {
+    /// Create a new instance of [`AnalyzerConfig`] with defaults.
+    fn default() -> Self {
+        AnalyzerConfig {
+            fft_size: 1024,
+            window: WindowFunction::Rectangular,
+            weighting: WeightingCurve::None,
+        }
+    }
+}
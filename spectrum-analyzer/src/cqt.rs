@@ -0,0 +1,132 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+use rustfft::{Fft, FftPlanner};
+use rustfft::num_complex::Complex;
+use crate::window::WindowFunction;
+
+/// The magnitude below which the spectral kernel's coefficients are dropped. The kernels are
+/// normalized to a peak of about one, so this discards the far side lobes of every atom.
+const KERNEL_THRESHOLD: f32 = 0.0054;
+
+/// The result of a constant-Q transform, as computed by
+/// [`Analyzer::process_cqt()`](crate::analyzer::Analyzer::process_cqt()).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CqtResult {
+    /// The center frequency in Hz of every bin. These are spaced geometrically, with a fixed
+    /// number of bins per octave.
+    pub center_freqs: Vec<f32>,
+    /// The magnitude of every bin. A sinusoid with an amplitude of `A` at a bin's center
+    /// frequency has a magnitude of `A / 2` in that bin.
+    pub magnitudes: Vec<f32>,
+}
+
+/// A precomputed constant-Q transform kernel, following Brown and Puckette. Every bin has a
+/// complex atom of a Hann window and a sinusoid at the bin's center frequency, with a length
+/// inversely proportional to that frequency so every bin has the same Q. The atoms are stored in
+/// the frequency domain, where they are sparse, so the transform is a single FFT of the frame
+/// followed by a short dot product per bin.
+pub struct CqtKernel {
+    /// The sample rate, bins per octave and lowest frequency the kernel was built for.
+    parameters: (f32, u32, f32),
+    fft: Arc<dyn Fft<f32>>,
+    center_freqs: Vec<f32>,
+    /// The non-negligible coefficients of every atom's conjugated spectrum, as bin indices and
+    /// values.
+    atoms: Vec<Vec<(usize, Complex<f32>)>>,
+}
+
+impl CqtKernel {
+    /// Build the kernel for bins from `f_min` up to the Nyquist frequency.
+    pub fn new(
+        fft_planner: &mut FftPlanner<f32>,
+        sample_rate: f32,
+        bins_per_octave: u32,
+        f_min: f32,
+    ) -> Self {
+        let bins_per_octave = bins_per_octave.max(1);
+        let f_min = f_min.max(1.0);
+        let q = 1.0 / (2.0_f32.powf(1.0 / bins_per_octave as f32) - 1.0);
+        let nyquist = sample_rate / 2.0;
+        let center_freqs = (0..)
+            .map(|bin| f_min * 2.0_f32.powf(bin as f32 / bins_per_octave as f32))
+            .take_while(|&frequency| frequency < nyquist)
+            .collect::<Vec<_>>();
+
+        // The lowest bin has the longest atom, which determines the frame size.
+        let fft_size = ((q * sample_rate / f_min).ceil() as usize).next_power_of_two();
+        let fft = fft_planner.plan_fft_forward(fft_size);
+        let mut spectrum = vec![Complex::new(0.0, 0.0); fft_size];
+        let atoms = center_freqs
+            .iter()
+            .map(|&frequency| {
+                let atom_len = ((q * sample_rate / frequency).ceil() as usize).min(fft_size);
+                let start = (fft_size - atom_len) / 2;
+                let window_sum = (0..atom_len)
+                    .map(|i| WindowFunction::Hann.coefficient(i, atom_len))
+                    .sum::<f32>();
+
+                spectrum.fill(Complex::new(0.0, 0.0));
+                for i in 0..atom_len {
+                    let window = WindowFunction::Hann.coefficient(i, atom_len) / window_sum;
+                    let phase = 2.0 * PI * frequency * i as f32 / sample_rate;
+                    spectrum[start + i] = Complex::new(window * phase.cos(), window * phase.sin());
+                }
+                fft.process(&mut spectrum);
+
+                spectrum
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, coefficient)| coefficient.norm() > KERNEL_THRESHOLD)
+                    .map(|(bin, coefficient)| (bin, coefficient.conj() / fft_size as f32))
+                    .collect()
+            })
+            .collect();
+
+        CqtKernel {
+            parameters: (sample_rate, bins_per_octave, f_min),
+            fft,
+            center_freqs,
+            atoms,
+        }
+    }
+
+    /// Whether this kernel was built for the given parameters.
+    pub fn matches(&self, sample_rate: f32, bins_per_octave: u32, f_min: f32) -> bool {
+        self.parameters == (sample_rate, bins_per_octave.max(1), f_min.max(1.0))
+    }
+
+    /// Transform the most recent frame of `samples`, padding it with zeros if it's too short.
+    pub(crate) fn transform(&self, samples: &[f32], scratch: &mut Vec<Complex<f32>>) -> CqtResult {
+        let fft_size = self.fft.len();
+        let frame = &samples[samples.len().saturating_sub(fft_size)..];
+        scratch.clear();
+        scratch.extend(
+            (0..fft_size).map(|i| Complex::new(frame.get(i).copied().unwrap_or(0.0), 0.0)),
+        );
+        self.fft.process(scratch);
+
+        let magnitudes = self
+            .atoms
+            .iter()
+            .map(|atom| {
+                atom.iter()
+                    .map(|&(bin, coefficient)| scratch[bin] * coefficient)
+                    .fold(Complex::new(0.0, 0.0), |sum, value| sum + value)
+                    .norm()
+            })
+            .collect();
+
+        CqtResult { center_freqs: self.center_freqs.clone(), magnitudes }
+    }
+}
+
+impl PartialEq for CqtKernel {
+    /// Whether both kernels have the same parameters and atoms, and so compute the same
+    /// transform. The FFT plans themselves can't be compared, only their sizes.
+    fn eq(&self, other: &CqtKernel) -> bool {
+        self.parameters == other.parameters
+            && self.fft.len() == other.fft.len()
+            && self.center_freqs == other.center_freqs
+            && self.atoms == other.atoms
+    }
+}
@@ -0,0 +1,195 @@
+use std::sync::{Arc, Mutex, PoisonError};
+use nih_plug::prelude::Editor;
+use nih_plug_egui::egui::{
+    pos2, Align2, Color32, FontId, Painter, Pos2, Rect, Sense, Shape, Stroke, Ui, Vec2,
+};
+use nih_plug_egui::resizable_window::ResizableWindow;
+use nih_plug_egui::{create_egui_editor, EguiState};
+use crate::analyzer::{amplitude_to_db, AnalyzerResult, DEFAULT_FLOOR_DB, DEFAULT_REFERENCE_A4};
+use crate::handoff::{ResultsOutput, REFERENCE_LABEL};
+use crate::notes::HeldNotes;
+
+/// The size of the editor when it's opened for the first time. After that, the size it was
+/// resized to is saved with the project.
+const DEFAULT_SIZE: (u32, u32) = (800, 400);
+/// The smallest size the editor can be resized to.
+const MIN_SIZE: Vec2 = Vec2::new(400.0, 200.0);
+
+/// The frequency range in Hz of the horizontal axis.
+pub const MIN_FREQUENCY: f32 = 20.0;
+pub const MAX_FREQUENCY: f32 = 20_000.0;
+/// The magnitude range in dB of the vertical axis. A full-scale sine reads 0 dB.
+const MIN_DB: f32 = -96.0;
+const MAX_DB: f32 = 0.0;
+
+/// The frequencies in Hz of the vertical grid lines, with their labels.
+const FREQUENCY_GRID: [(f32, &str); 3] = [(100.0, "100"), (1000.0, "1k"), (10_000.0, "10k")];
+/// The distance in dB between the horizontal grid lines.
+const DB_GRID_STEP: f32 = 12.0;
+/// The color of the grid lines and their labels.
+const GRID_COLOR: Color32 = Color32::DARK_GRAY;
+/// The size of the labels of the grid lines and of the cursor readout.
+const LABEL_SIZE: f32 = 11.0;
+/// The color of the cursor readout.
+const READOUT_COLOR: Color32 = Color32::WHITE;
+
+/// The colors of the channels' lines, which repeat for layouts with more channels.
+const CHANNEL_COLORS: [Color32; 4] = [
+    Color32::from_rgb(80, 200, 255),
+    Color32::from_rgb(255, 160, 60),
+    Color32::from_rgb(120, 230, 120),
+    Color32::from_rgb(230, 110, 200),
+];
+/// The color of the lines of the reference signal on the sidechain.
+const REFERENCE_COLOR: Color32 = Color32::GRAY;
+/// The color of the markers of held MIDI notes.
+const MARKER_COLOR: Color32 = Color32::from_rgba_unmultiplied(255, 255, 255, 96);
+/// The number of harmonics that are marked above the fundamental of every held note.
+const MARKER_HARMONICS: u32 = 4;
+
+/// Get the state of an editor that hasn't been opened yet.
+pub fn default_state() -> Arc<EguiState> {
+    EguiState::from_size(DEFAULT_SIZE.0, DEFAULT_SIZE.1)
+}
+
+/// Create the editor, which draws the magnitudes of the latest results of every channel as a
+/// line on a logarithmic frequency axis, with the markers of the held MIDI notes on top. The
+/// results are read from the editor's side of the results channel, so the audio thread never
+/// waits for the editor. The editor's capture button calls `capture_reference`.
+pub fn create(
+    editor_state: Arc<EguiState>,
+    results: Arc<Mutex<ResultsOutput>>,
+    held_notes: Arc<HeldNotes>,
+    capture_reference: impl Fn() + Send + Sync + 'static,
+) -> Option<Box<dyn Editor>> {
+    create_egui_editor(
+        editor_state.clone(),
+        (),
+        |_, _| {},
+        move |egui_ctx, _setter, _state| {
+            ResizableWindow::new("spectrum-analyzer")
+                .min_size(MIN_SIZE)
+                .show(egui_ctx, &editor_state, |ui| {
+                    if ui.button("Capture Reference").clicked() {
+                        capture_reference();
+                    }
+                    draw(ui, &results, &held_notes);
+                });
+
+            // New results arrive with every block, so the editor keeps redrawing at the host's
+            // frame rate rather than waiting for input.
+            egui_ctx.request_repaint();
+        },
+    )
+}
+
+/// Draw the spectrum of the latest results and the markers of the held notes, filling the
+/// space that's left in `ui`. While the pointer is over the spectrum, the frequency and the
+/// magnitude under it are shown next to it.
+fn draw(ui: &mut Ui, results: &Mutex<ResultsOutput>, held_notes: &HeldNotes) {
+    let (response, painter) = ui.allocate_painter(ui.available_size(), Sense::hover());
+    let rect = response.rect;
+    draw_grid(&painter, rect);
+
+    let mut results = results.lock().unwrap_or_else(PoisonError::into_inner);
+    let results = results.read();
+    for (channel, result) in results.iter().enumerate() {
+        let color = if result.label.as_deref() == Some(REFERENCE_LABEL) {
+            REFERENCE_COLOR
+        } else {
+            CHANNEL_COLORS[channel % CHANNEL_COLORS.len()]
+        };
+        painter.add(Shape::line(spectrum_points(result, rect), Stroke::new(1.5, color)));
+    }
+
+    for marker in held_notes.markers(DEFAULT_REFERENCE_A4, MARKER_HARMONICS, MAX_FREQUENCY) {
+        let x = frequency_to_x(marker.frequency, rect.left(), rect.width());
+        let width = if marker.harmonic == 1 { 1.5 } else { 0.75 };
+        painter.line_segment(
+            [pos2(x, rect.top()), pos2(x, rect.bottom())],
+            Stroke::new(width, MARKER_COLOR),
+        );
+    }
+
+    // The readout shows the first channel, interpolated between the bins around the pointer.
+    // Like the line, it shows silent bins at the floor rather than at negative infinity.
+    let pointer = response.hover_pos().filter(|pointer| rect.contains(*pointer));
+    if let (Some(pointer), Some(result)) = (pointer, results.first()) {
+        let frequency = x_to_frequency(pointer.x, rect.left(), rect.width());
+        let magnitude = result.magnitude_at(frequency);
+        painter.text(
+            pos2(pointer.x + 8.0, pointer.y - 8.0),
+            Align2::LEFT_BOTTOM,
+            format!("{frequency:.0} Hz, {:.1} dB", amplitude_to_db(magnitude, DEFAULT_FLOOR_DB)),
+            FontId::proportional(LABEL_SIZE),
+            READOUT_COLOR,
+        );
+    }
+}
+
+/// Draw the grid lines at the frequencies of [`FREQUENCY_GRID`] and every [`DB_GRID_STEP`] dB,
+/// with labels along the bottom and left edges.
+fn draw_grid(painter: &Painter, rect: Rect) {
+    let stroke = Stroke::new(1.0, GRID_COLOR);
+    for (frequency, label) in FREQUENCY_GRID {
+        let x = frequency_to_x(frequency, rect.left(), rect.width());
+        painter.line_segment([pos2(x, rect.top()), pos2(x, rect.bottom())], stroke);
+        painter.text(
+            pos2(x + 2.0, rect.bottom()),
+            Align2::LEFT_BOTTOM,
+            label,
+            FontId::proportional(LABEL_SIZE),
+            GRID_COLOR,
+        );
+    }
+
+    let mut db = MAX_DB - DB_GRID_STEP;
+    while db > MIN_DB {
+        let y = db_to_y(db, rect);
+        painter.line_segment([pos2(rect.left(), y), pos2(rect.right(), y)], stroke);
+        painter.text(
+            pos2(rect.left() + 2.0, y),
+            Align2::LEFT_BOTTOM,
+            format!("{db} dB"),
+            FontId::proportional(LABEL_SIZE),
+            GRID_COLOR,
+        );
+        db -= DB_GRID_STEP;
+    }
+}
+
+/// Get the points of the line of a result, for the bins within the frequency range.
+fn spectrum_points(result: &AnalyzerResult, rect: Rect) -> Vec<Pos2> {
+    result
+        .frequencies
+        .iter()
+        .zip(&result.magnitudes)
+        .filter(|(&frequency, _)| (MIN_FREQUENCY..=MAX_FREQUENCY).contains(&frequency))
+        .map(|(&frequency, &magnitude)| {
+            let db = amplitude_to_db(magnitude, DEFAULT_FLOOR_DB);
+            pos2(frequency_to_x(frequency, rect.left(), rect.width()), db_to_y(db, rect))
+        })
+        .collect()
+}
+
+/// Map a frequency in Hz to a horizontal position in pixels on an axis that starts at `left`
+/// and is `width` pixels wide. The axis is logarithmic, from [`MIN_FREQUENCY`] at the left edge
+/// to [`MAX_FREQUENCY`] at the right edge, so every decade is equally wide. Frequencies outside
+/// that range map to positions outside the axis.
+pub fn frequency_to_x(frequency: f32, left: f32, width: f32) -> f32 {
+    let position = (frequency / MIN_FREQUENCY).log10() / (MAX_FREQUENCY / MIN_FREQUENCY).log10();
+    left + position * width
+}
+
+/// Map a horizontal position in pixels on the axis of [`frequency_to_x()`] back to a frequency
+/// in Hz.
+pub fn x_to_frequency(x: f32, left: f32, width: f32) -> f32 {
+    let position = (x - left) / width;
+    MIN_FREQUENCY * (MAX_FREQUENCY / MIN_FREQUENCY).powf(position)
+}
+
+/// Map a magnitude in dB to a vertical position in `rect`, clamped to the range of the axis.
+fn db_to_y(db: f32, rect: Rect) -> f32 {
+    let position = (db.clamp(MIN_DB, MAX_DB) - MIN_DB) / (MAX_DB - MIN_DB);
+    rect.bottom() - position * rect.height()
+}
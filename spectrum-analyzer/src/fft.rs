@@ -0,0 +1,106 @@
+use std::sync::Arc;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+
+/// Plans the forward FFTs the analyzer transforms its frames with. The analyzer only ever
+/// transforms real signals, so a backend can use a real-to-complex transform. Which backend is
+/// used is chosen at compile time, see [`DefaultFftBackend`].
+pub(crate) trait FftBackend {
+    /// Plan a forward FFT of `len` samples. Everything the FFT needs is allocated here, so
+    /// [`ForwardFft::process()`] doesn't allocate.
+    fn plan(&mut self, len: usize) -> Box<dyn ForwardFft>;
+}
+
+/// A forward FFT of a fixed length, as planned by an [`FftBackend`].
+pub(crate) trait ForwardFft: Send {
+    /// Get the number of samples the FFT transforms.
+    fn len(&self) -> usize;
+
+    /// Transform `buffer` in place into its spectrum. The imaginary parts of the samples must be
+    /// zero, as a backend may only read the real parts. Only the bins up to and including the
+    /// Nyquist bin are computed, the rest of the buffer is left as is. Use [`mirror_spectrum()`]
+    /// to get the full spectrum.
+    fn process(&mut self, buffer: &mut [Complex<f32>]);
+}
+
+/// The backend the analyzer uses: a real-to-complex transform from realfft, which does about
+/// half the work of a complex FFT, or rustfft's complex FFT without the `realfft` feature.
+#[cfg(not(feature = "realfft"))]
+pub(crate) type DefaultFftBackend = FftPlanner<f32>;
+#[cfg(feature = "realfft")]
+pub(crate) type DefaultFftBackend = realfft::RealFftPlanner<f32>;
+
+impl FftBackend for FftPlanner<f32> {
+    fn plan(&mut self, len: usize) -> Box<dyn ForwardFft> {
+        let fft = self.plan_fft_forward(len);
+        Box::new(ComplexFft {
+            scratch: vec![Complex::new(0.0, 0.0); fft.get_inplace_scratch_len()],
+            fft,
+        })
+    }
+}
+
+/// A complex FFT with its scratch buffer. [`Fft::process()`] would allocate the scratch buffer
+/// on every call.
+struct ComplexFft {
+    fft: Arc<dyn Fft<f32>>,
+    scratch: Vec<Complex<f32>>,
+}
+
+impl ForwardFft for ComplexFft {
+    fn len(&self) -> usize {
+        rustfft::Length::len(self.fft.as_ref())
+    }
+
+    fn process(&mut self, buffer: &mut [Complex<f32>]) {
+        self.fft.process_with_scratch(buffer, &mut self.scratch);
+    }
+}
+
+#[cfg(feature = "realfft")]
+impl FftBackend for realfft::RealFftPlanner<f32> {
+    fn plan(&mut self, len: usize) -> Box<dyn ForwardFft> {
+        let fft = self.plan_fft_forward(len);
+        Box::new(RealFft {
+            input: fft.make_input_vec(),
+            output: fft.make_output_vec(),
+            scratch: fft.make_scratch_vec(),
+            fft,
+        })
+    }
+}
+
+/// A real-to-complex FFT with the buffers it transforms through.
+#[cfg(feature = "realfft")]
+struct RealFft {
+    fft: Arc<dyn realfft::RealToComplex<f32>>,
+    input: Vec<f32>,
+    output: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+}
+
+#[cfg(feature = "realfft")]
+impl ForwardFft for RealFft {
+    fn len(&self) -> usize {
+        self.fft.len()
+    }
+
+    fn process(&mut self, buffer: &mut [Complex<f32>]) {
+        for (input, sample) in self.input.iter_mut().zip(buffer.iter()) {
+            *input = sample.re;
+        }
+        // The buffers were made by the FFT itself, so their lengths always match.
+        let _ = self.fft.process_with_scratch(&mut self.input, &mut self.output, &mut self.scratch);
+        buffer[..self.output.len()].copy_from_slice(&self.output);
+    }
+}
+
+/// Fill in the bins above the Nyquist bin of a spectrum transformed by [`ForwardFft::process()`].
+/// The spectrum of a real signal is conjugate symmetric, so bin `k` is the complex conjugate of
+/// bin `len - k`.
+pub(crate) fn mirror_spectrum(spectrum: &mut [Complex<f32>]) {
+    let len = spectrum.len();
+    for bin in len / 2 + 1..len {
+        spectrum[bin] = spectrum[len - bin].conj();
+    }
+}
@@ -0,0 +1,96 @@
+use triple_buffer::{Input, Output, TripleBuffer};
+use crate::analyzer::AnalyzerResult;
+
+/// The label of the results of the reference signal, see [`ResultsInput::publish()`].
+pub const REFERENCE_LABEL: &str = "Reference";
+
+/// Create a channel for handing the latest analysis results from the audio thread to the editor.
+/// The channel is a triple buffer, so neither side ever waits for the other: the audio thread
+/// writes into a back buffer and publishes it, and the editor always reads the most recently
+/// published results. Results that are published while the editor isn't reading are simply
+/// replaced by newer ones.
+///
+/// All three buffers start out with room for `channel_count` results and `reference_count`
+/// reference results of `bin_count` bins, so publishing results of up to that size never
+/// allocates.
+pub fn results_channel(
+    channel_count: usize,
+    reference_count: usize,
+    bin_count: usize,
+) -> (ResultsInput, ResultsOutput) {
+    let result = |label: Option<&str>| AnalyzerResult {
+        frequencies: vec![0.0; bin_count],
+        magnitudes: vec![0.0; bin_count],
+        label: label.map(String::from),
+        channel: 0,
+        phases: None,
+        start_bin: 0,
+        rms: 0.0,
+        peak: 0.0,
+    };
+    let mut template = vec![result(None); channel_count];
+    template.extend(std::iter::repeat_with(|| result(Some(REFERENCE_LABEL))).take(reference_count));
+    let (input, output) = TripleBuffer::new(&template).split();
+
+    (ResultsInput { input }, ResultsOutput { output, has_results: false })
+}
+
+/// The audio thread's side of a [`results_channel()`].
+pub struct ResultsInput {
+    input: Input<Vec<AnalyzerResult>>,
+}
+
+/// The editor's side of a [`results_channel()`].
+pub struct ResultsOutput {
+    output: Output<Vec<AnalyzerResult>>,
+    /// Whether anything has been published yet. Until then the buffers only hold the template
+    /// the channel was created with, which is not a real analysis.
+    has_results: bool,
+}
+
+impl ResultsInput {
+    /// Make `results` and the results of the `reference` signal the latest results the editor
+    /// reads. The reference results follow the other results and are labeled
+    /// [`REFERENCE_LABEL`]. Everything is copied into the back buffer, whose vectors are reused,
+    /// so this is realtime safe as long as the results fit in the size the channel was created
+    /// with.
+    pub fn publish(&mut self, results: &[AnalyzerResult], reference: &[AnalyzerResult]) {
+        let back = self.input.input_buffer_mut();
+        back.truncate(results.len() + reference.len());
+        for (target, source) in back.iter_mut().zip(results) {
+            target.clone_from(source);
+        }
+        let copied = back.len().min(results.len());
+        back.extend_from_slice(&results[copied..]);
+
+        // The reference results keep their label, so it doesn't have to be copied every time.
+        let copied = back.len() - results.len();
+        for (target, source) in back[results.len()..].iter_mut().zip(reference) {
+            target.frequencies.clone_from(&source.frequencies);
+            target.magnitudes.clone_from(&source.magnitudes);
+            target.phases.clone_from(&source.phases);
+            target.channel = source.channel;
+            target.start_bin = source.start_bin;
+            (target.rms, target.peak) = (source.rms, source.peak);
+            if target.label.as_deref() != Some(REFERENCE_LABEL) {
+                target.label = Some(String::from(REFERENCE_LABEL));
+            }
+        }
+        back.extend(reference[copied..].iter().map(|source| AnalyzerResult {
+            label: Some(String::from(REFERENCE_LABEL)),
+            ..source.clone()
+        }));
+
+        self.input.publish();
+    }
+}
+
+impl ResultsOutput {
+    /// Get the most recently published results, or nothing if no results have been published
+    /// yet. This never blocks the audio thread.
+    pub fn read(&mut self) -> &[AnalyzerResult] {
+        self.has_results |= self.output.updated();
+        let results = self.output.read();
+        if self.has_results { results } else { &[] }
+    }
+}
@@ -1,3 +1,19 @@
 pub mod plugin;
 pub mod analyzer;
-
+pub mod analyzer_f64;
+pub mod builder;
+pub mod config;
+pub mod cqt;
+pub mod editor;
+mod fft;
+pub mod handoff;
+pub mod mel;
+pub mod notes;
+pub mod settings;
+pub mod state;
+pub mod sweep;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testsignals;
+pub mod transfer;
+pub mod weighting;
+pub mod window;
@@ -1,8 +1,83 @@
 use nih_plug::prelude::*;
 use spectrum_analyzer::plugin::SpectrumAnalyzer;
 
+/// Get the arguments for the standalone wrapper, starting with `program`, from the command line
+/// arguments without the program name. The wrapper already has the options to reproduce a
+/// host's setup: `-r`/`--sample-rate`, `-p`/`--period-size` and `--input-device`, among others.
+/// These are passed on as they are, so the wrapper's defaults apply to the options that aren't
+/// given. `--block-size`, the name the analyzer uses for the same thing, is accepted as another
+/// name for `--period-size`.
+fn wrapper_args(program: String, args: impl IntoIterator<Item = String>) -> Vec<String> {
+    std::iter::once(program)
+        .chain(args.into_iter().map(|arg| match arg.strip_prefix("--block-size") {
+            Some(value) if value.is_empty() || value.starts_with('=') => {
+                format!("--period-size{value}")
+            }
+            _ => arg,
+        }))
+        .collect()
+}
+
 /// The main function for the plugin. This makes it possible to build the plugin as a standalone
-/// executable.
+/// executable. Run it with `--sample-rate`, `--block-size` (or `--period-size`) and
+/// `--input-device` to choose the conditions it runs under, see [`wrapper_args()`].
 fn main() {
-    nih_export_standalone::<SpectrumAnalyzer>();
-}
\ No newline at end of file
+    let mut args = std::env::args();
+    let program = args.next().unwrap_or_else(|| String::from("spectrum-analyzer"));
+    nih_export_standalone_with_args::<SpectrumAnalyzer, _>(wrapper_args(program, args));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::wrapper_args;
+
+    /// Turn string slices into the owned arguments [`wrapper_args()`] takes.
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().copied().map(String::from).collect()
+    }
+
+    #[test]
+    fn block_size_is_passed_on_as_the_period_size() {
+        // Arrange
+        let separate = args(&["--block-size", "256"]);
+        let joined = args(&["--block-size=256"]);
+
+        // Act
+        let separate = wrapper_args(String::from("standalone"), separate);
+        let joined = wrapper_args(String::from("standalone"), joined);
+
+        // Assert
+        assert_eq!(separate, args(&["standalone", "--period-size", "256"]));
+        assert_eq!(joined, args(&["standalone", "--period-size=256"]));
+    }
+
+    #[test]
+    fn the_wrappers_own_options_are_passed_on_once_and_unchanged() {
+        // Arrange
+        let given = args(&[
+            "-r",
+            "44100",
+            "--period-size=128",
+            "--input-device",
+            "Loopback",
+            "--backend",
+            "jack",
+        ]);
+
+        // Act
+        let passed = wrapper_args(String::from("standalone"), given.clone());
+
+        // Assert
+        assert_eq!(passed[0], "standalone");
+        assert_eq!(passed[1..], given[..]);
+    }
+
+    #[test]
+    fn nothing_is_added_without_arguments() {
+        // Act
+        let passed = wrapper_args(String::from("standalone"), Vec::new());
+
+        // Assert
+        assert_eq!(passed, args(&["standalone"]));
+    }
+}
@@ -0,0 +1,105 @@
+/// The result of applying a mel filterbank to a spectrum, as computed by
+/// [`Analyzer::process_mel()`](crate::analyzer::Analyzer::process_mel()).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MelResult {
+    /// The energy in every mel band, which is the power spectrum weighted by the band's filter.
+    pub energies: Vec<f32>,
+    /// The center frequency in Hz of every mel band.
+    pub center_freqs: Vec<f32>,
+}
+
+/// Convert a frequency in Hz to mels, using the common `2595 * log10(1 + f / 700)` definition.
+pub fn hz_to_mel(frequency: f32) -> f32 {
+    2595.0 * (1.0 + frequency / 700.0).log10()
+}
+
+/// Convert a pitch in mels to a frequency in Hz. This is the inverse of [`hz_to_mel()`].
+pub fn mel_to_hz(mel: f32) -> f32 {
+    700.0 * (10.0_f32.powf(mel / 2595.0) - 1.0)
+}
+
+/// A bank of triangular filters that are spaced evenly on the mel scale. Every filter rises
+/// linearly from the center of the band below it to a weight of one at its own center, and
+/// falls back to zero at the center of the band above it.
+pub(crate) struct MelFilterbank {
+    /// The bin width, the number of bins, the number of bands and the frequency range the
+    /// filterbank was built for.
+    parameters: (f32, usize, usize, f32, f32),
+    center_freqs: Vec<f32>,
+    /// The first bin every filter covers, and its weights for the bins from there on.
+    filters: Vec<(usize, Vec<f32>)>,
+}
+
+impl MelFilterbank {
+    /// Build `num_mels` filters between `f_min` and `f_max` for a spectrum of `bin_count` bins
+    /// that are `bin_width` Hz apart.
+    pub(crate) fn new(
+        bin_width: f32,
+        bin_count: usize,
+        num_mels: usize,
+        f_min: f32,
+        f_max: f32,
+    ) -> Self {
+        let (mel_min, mel_max) = (hz_to_mel(f_min), hz_to_mel(f_max));
+        let edges = (0..num_mels + 2)
+            .map(|i| mel_to_hz(mel_min + (mel_max - mel_min) * i as f32 / (num_mels + 1) as f32))
+            .collect::<Vec<_>>();
+
+        let filters = edges
+            .windows(3)
+            .map(|band| {
+                let (lower, center, upper) = (band[0], band[1], band[2]);
+                let first = ((lower / bin_width).ceil() as usize).min(bin_count);
+                let last = ((upper / bin_width).floor() as usize + 1).clamp(first, bin_count);
+                let weights = (first..last)
+                    .map(|bin| {
+                        let frequency = bin as f32 * bin_width;
+                        if frequency <= center {
+                            (frequency - lower) / (center - lower)
+                        } else {
+                            (upper - frequency) / (upper - center)
+                        }
+                    })
+                    .map(|weight| weight.max(0.0))
+                    .collect();
+
+                (first, weights)
+            })
+            .collect();
+
+        MelFilterbank {
+            parameters: (bin_width, bin_count, num_mels, f_min, f_max),
+            center_freqs: edges[1..=num_mels].to_vec(),
+            filters,
+        }
+    }
+
+    /// Whether this filterbank was built for the given parameters.
+    pub(crate) fn matches(
+        &self,
+        bin_width: f32,
+        bin_count: usize,
+        num_mels: usize,
+        f_min: f32,
+        f_max: f32,
+    ) -> bool {
+        self.parameters == (bin_width, bin_count, num_mels, f_min, f_max)
+    }
+
+    /// Apply the filterbank to the power of the given magnitudes.
+    pub(crate) fn apply(&self, magnitudes: &[f32]) -> MelResult {
+        let energies = self
+            .filters
+            .iter()
+            .map(|(first, weights)| {
+                weights
+                    .iter()
+                    .zip(&magnitudes[*first..])
+                    .map(|(weight, magnitude)| weight * magnitude * magnitude)
+                    .sum()
+            })
+            .collect();
+
+        MelResult { energies, center_freqs: self.center_freqs.clone() }
+    }
+}
@@ -0,0 +1,108 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A marker for a held MIDI note, at the frequency of its fundamental or one of its harmonics, as
+/// returned by [`HeldNotes::markers()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoteMarker {
+    /// The MIDI note number that is held.
+    pub note: u8,
+    /// The harmonic the marker is at, where `1` is the fundamental.
+    pub harmonic: u32,
+    /// The frequency of the marker in Hz.
+    pub frequency: f32,
+}
+
+/// The MIDI notes that are currently held, shared between the audio thread and the editor. The
+/// audio thread updates the notes from the note events it receives, and the editor draws
+/// markers at their frequencies on top of the spectrum. The notes are stored as a bit set of
+/// atomics, so neither side ever locks or allocates. Notes are tracked by number only, so the
+/// same note held on two MIDI channels is released by the first note off.
+#[derive(Debug, Default)]
+pub struct HeldNotes {
+    bits: [AtomicU64; 2],
+}
+
+/// Convert a MIDI note number to the frequency in Hz of its fundamental, in equal temperament
+/// relative to `reference_a4`, which is normally
+/// [`DEFAULT_REFERENCE_A4`](crate::analyzer::DEFAULT_REFERENCE_A4).
+pub fn note_to_hz(note: u8, reference_a4: f32) -> f32 {
+    // A4 is MIDI note 69.
+    reference_a4 * 2.0_f32.powf((note as f32 - 69.0) / 12.0)
+}
+
+/// The lowest frequency in Hz [`frequency_to_cc()`] maps, to a CC value of 0.
+pub const CC_MIN_FREQUENCY: f32 = 20.0;
+/// The highest frequency in Hz [`frequency_to_cc()`] maps, to a CC value of 127.
+pub const CC_MAX_FREQUENCY: f32 = 20_000.0;
+
+/// Map a frequency in Hz to a 7-bit MIDI CC value, logarithmically from [`CC_MIN_FREQUENCY`] at
+/// 0 to [`CC_MAX_FREQUENCY`] at 127, so every octave gets about the same number of steps.
+/// Frequencies outside that range are clamped.
+pub fn frequency_to_cc(frequency: f32) -> u8 {
+    let position = (frequency.max(CC_MIN_FREQUENCY) / CC_MIN_FREQUENCY).log2()
+        / (CC_MAX_FREQUENCY / CC_MIN_FREQUENCY).log2();
+    (position.clamp(0.0, 1.0) * 127.0).round() as u8
+}
+
+impl HeldNotes {
+    /// Mark `note` as held. Note numbers above 127 are ignored.
+    pub fn note_on(&self, note: u8) {
+        if let Some((word, mask)) = Self::position(note) {
+            self.bits[word].fetch_or(mask, Ordering::Relaxed);
+        }
+    }
+
+    /// Mark `note` as released.
+    pub fn note_off(&self, note: u8) {
+        if let Some((word, mask)) = Self::position(note) {
+            self.bits[word].fetch_and(!mask, Ordering::Relaxed);
+        }
+    }
+
+    /// Release every note.
+    pub fn clear(&self) {
+        for word in &self.bits {
+            word.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Whether `note` is held.
+    pub fn is_held(&self, note: u8) -> bool {
+        Self::position(note)
+            .is_some_and(|(word, mask)| self.bits[word].load(Ordering::Relaxed) & mask != 0)
+    }
+
+    /// Get the held notes, from low to high.
+    pub fn notes(&self) -> Vec<u8> {
+        (0..128).filter(|&note| self.is_held(note)).collect()
+    }
+
+    /// Get a marker for the fundamental and the first `num_harmonics` harmonics of every held
+    /// note, from low to high note. Markers at or above `max_frequency`, which is normally the
+    /// Nyquist frequency, are left out.
+    pub fn markers(
+        &self,
+        reference_a4: f32,
+        num_harmonics: u32,
+        max_frequency: f32,
+    ) -> Vec<NoteMarker> {
+        self.notes()
+            .into_iter()
+            .flat_map(|note| {
+                let fundamental = note_to_hz(note, reference_a4);
+                (1..=num_harmonics + 1)
+                    .map(move |harmonic| NoteMarker {
+                        note,
+                        harmonic,
+                        frequency: fundamental * harmonic as f32,
+                    })
+                    .take_while(|marker| marker.frequency < max_frequency)
+            })
+            .collect()
+    }
+
+    /// Get the word and bit mask of `note` in the bit set, or `None` if it isn't a MIDI note.
+    fn position(note: u8) -> Option<(usize, u64)> {
+        (note < 128).then(|| (note as usize / 64, 1 << (note % 64)))
+    }
+}
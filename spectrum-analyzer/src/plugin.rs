@@ -1,31 +1,352 @@
-use std::sync::Arc;
+use std::collections::BTreeMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use nih_plug_egui::EguiState;
+use rustfft::FftPlanner;
+use nih_plug::nih_debug_assert_failure;
 use nih_plug::prelude::*;
+use nih_plug::wrapper::state::ParamValue;
+use crate::analyzer::Analyzer;
+use crate::cqt::CqtKernel;
+use crate::editor;
+use crate::handoff::{results_channel, ResultsInput, ResultsOutput, REFERENCE_LABEL};
+use crate::notes::{frequency_to_cc, HeldNotes};
+use crate::state::{self, ReferenceCurve};
+use crate::window::WindowFunction;
+
+/// The largest tilt in dB per octave, in either direction, that the slope parameter allows.
+const MAX_SLOPE_DB_PER_OCTAVE: f32 = 6.0;
+
+/// The number of bins per octave of the constant-Q transform.
+const CQT_BINS_PER_OCTAVE: u32 = 12;
+/// The lowest frequency in Hz of the constant-Q transform, which is C1.
+const CQT_F_MIN: f32 = 32.703;
+
+/// The level in dB below which a block is silent and isn't analyzed, see
+/// [`Analyzer::set_silence_threshold()`]. This is well below what the editor shows.
+const SILENCE_THRESHOLD_DB: f32 = -120.0;
+
+/// The highest CC number the dominant peak can be sent on. The CCs above it are channel mode
+/// messages.
+const MAX_CC: i32 = 119;
 
 /// The parameters of the plugin. This struct will be used to store the parameters of the plugin.
+/// The ids are what the parameters are saved with in a project, so they must never change.
 #[derive(Params)]
-pub struct SpectrumAnalyzerParams {}
+pub struct SpectrumAnalyzerParams {
+    /// The number of samples in a frame.
+    #[id = "fft_size"]
+    pub fft_size: EnumParam<FftSize>,
+    /// The window function that is applied to a frame before it is transformed.
+    #[id = "window"]
+    pub window: EnumParam<WindowFunction>,
+    /// How much of the previous frame is kept in every new frame, see
+    /// [`Analyzer::set_smoothing()`].
+    #[id = "smoothing"]
+    pub smoothing: FloatParam,
+    /// The tilt in dB per octave that is applied to the spectrum.
+    #[id = "slope"]
+    pub slope: FloatParam,
+    /// Whether the display is held at the current results.
+    #[id = "freeze"]
+    pub freeze: BoolParam,
+    /// The MIDI CC number the frequency of the dominant peak is sent on, see
+    /// [`frequency_to_cc()`].
+    #[id = "peak_cc"]
+    pub peak_cc: IntParam,
+    /// The captured reference curve, encoded with [`ReferenceCurve::encode()`], or an empty
+    /// string if none has been captured. This is saved with the project, under the `persist` key
+    /// in [`PluginState::fields`], so the key must never change.
+    #[persist = "reference_curve"]
+    pub reference_curve: Arc<RwLock<String>>,
+    /// The size of the editor, which is saved with the project.
+    #[persist = "editor_state"]
+    pub editor_state: Arc<EguiState>,
+}
+
+/// The FFT sizes that can be chosen with [`SpectrumAnalyzerParams::fft_size`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum FftSize {
+    #[id = "512"]
+    #[name = "512"]
+    Size512,
+    #[id = "1024"]
+    #[name = "1024"]
+    Size1024,
+    #[id = "2048"]
+    #[name = "2048"]
+    Size2048,
+    #[id = "4096"]
+    #[name = "4096"]
+    Size4096,
+    #[id = "8192"]
+    #[name = "8192"]
+    Size8192,
+}
+
+impl FftSize {
+    /// Every FFT size that can be chosen, from small to large.
+    pub const ALL: [FftSize; 5] = [
+        FftSize::Size512,
+        FftSize::Size1024,
+        FftSize::Size2048,
+        FftSize::Size4096,
+        FftSize::Size8192,
+    ];
+
+    /// Get the number of samples in a frame.
+    pub fn samples(self) -> usize {
+        match self {
+            FftSize::Size512 => 512,
+            FftSize::Size1024 => 1024,
+            FftSize::Size2048 => 2048,
+            FftSize::Size4096 => 4096,
+            FftSize::Size8192 => 8192,
+        }
+    }
+
+    /// Get the supported FFT size that is closest to `samples` on a logarithmic scale.
+    pub fn nearest(samples: usize) -> FftSize {
+        let distance = |fft_size: &FftSize| {
+            ((fft_size.samples() as f32).log2() - (samples.max(1) as f32).log2()).abs()
+        };
+
+        FftSize::ALL
+            .into_iter()
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .unwrap_or(FftSize::Size2048)
+    }
+}
+
+/// The work the plugin hands off to a background thread, as it's too expensive for the audio
+/// thread.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnalyzerTask {
+    /// Compute the kernel of a constant-Q transform with these parameters, see
+    /// [`CqtKernel::new()`]. The kernel is installed in the analyzer at the start of a later
+    /// block. The plugin only starts this while the constant-Q transform is shown, see
+    /// [`SpectrumAnalyzer::set_cqt_enabled()`].
+    RecomputeCqtKernel { sample_rate: f32, bins_per_octave: u32, f_min: f32 },
+    /// Capture the latest results as the reference curve, see
+    /// [`SpectrumAnalyzer::capture_reference()`]. The editor starts this, as it has to lock the
+    /// results and allocate the encoded curve.
+    CaptureReference { fft_size: usize },
+}
 
 /// The plugin itself. This struct will be used to store the state of the plugin.
 pub struct SpectrumAnalyzer {
     params: Arc<SpectrumAnalyzerParams>,
+    /// The analyzer that every block of audio is fed to.
+    analyzer: Analyzer,
+    /// The analyzer for the reference signal on the sidechain input, if the layout has one.
+    reference_analyzer: Analyzer,
+    /// The audio thread's side of the channel the results are handed to the editor through.
+    results_input: ResultsInput,
+    /// The editor's side of that channel. It's replaced by a channel of the right size when the
+    /// plugin is initialized, which an open editor picks up since it shares the mutex.
+    results_output: Arc<Mutex<ResultsOutput>>,
+    /// The MIDI notes that are held, which the editor draws markers for.
+    held_notes: Arc<HeldNotes>,
+    /// The CC number and value the dominant peak was last sent as, so a CC is only sent when
+    /// either changes.
+    last_peak_cc: Option<(u8, u8)>,
+    /// The channel the background thread sends the kernels it computes through. The sender is
+    /// kept to be cloned into the task executor.
+    cqt_kernel_sender: Sender<CqtKernel>,
+    cqt_kernel_receiver: Receiver<CqtKernel>,
+    /// Whether the constant-Q transform is shown, so the analyzer needs a kernel for it.
+    cqt_enabled: bool,
+    /// Whether a [`AnalyzerTask::RecomputeCqtKernel`] task has been started and its kernel hasn't
+    /// been installed yet, so the audio thread doesn't start another one every block.
+    cqt_kernel_pending: bool,
 }
 
 impl Default for SpectrumAnalyzerParams {
     /// Create a new instance of [`SpectrumAnalyzerParams`] with defaults.
     fn default() -> Self {
-        SpectrumAnalyzerParams {}
+        SpectrumAnalyzerParams {
+            fft_size: EnumParam::new("FFT Size", FftSize::Size2048),
+            window: EnumParam::new("Window", WindowFunction::Hann),
+            smoothing: FloatParam::new("Smoothing", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 })
+                .with_step_size(0.01),
+            slope: FloatParam::new(
+                "Slope",
+                0.0,
+                FloatRange::Linear { min: -MAX_SLOPE_DB_PER_OCTAVE, max: MAX_SLOPE_DB_PER_OCTAVE },
+            )
+                .with_unit(" dB/oct")
+                .with_step_size(0.1),
+            freeze: BoolParam::new("Freeze", false),
+            peak_cc: IntParam::new(
+                "Peak CC",
+                control_change::GENERAL_PURPOSE_CONTROLLER_1 as i32,
+                IntRange::Linear { min: 0, max: MAX_CC },
+            ),
+            reference_curve: Arc::new(RwLock::new(String::new())),
+            editor_state: editor::default_state(),
+        }
     }
 }
 
+impl SpectrumAnalyzerParams {
+    /// Get the default value of every parameter, the way it's stored in a saved state.
+    fn default_values(&self) -> BTreeMap<String, ParamValue> {
+        let fft_size = self.fft_size.default_plain_value().samples().to_string();
+        let window = window_id(self.window.default_plain_value());
+
+        BTreeMap::from([
+            (String::from("fft_size"), ParamValue::String(fft_size)),
+            (String::from("window"), ParamValue::String(window)),
+            (String::from("smoothing"), ParamValue::F32(self.smoothing.default_plain_value())),
+            (String::from("slope"), ParamValue::F32(self.slope.default_plain_value())),
+            (String::from("freeze"), ParamValue::Bool(self.freeze.default_plain_value())),
+            (String::from("peak_cc"), ParamValue::I32(self.peak_cc.default_plain_value())),
+        ])
+    }
+}
+
+/// Get the id a window is saved with.
+fn window_id(window: WindowFunction) -> String {
+    let ids = WindowFunction::ids().unwrap_or_default();
+    ids.get(window.to_index()).copied().unwrap_or_default().into()
+}
+
 impl Default for SpectrumAnalyzer {
     /// Create a new instance of [`SpectrumAnalyzer`] with defaults.
     fn default() -> Self {
+        let (results_input, results_output) = results_channel(0, 0, 0);
+        let (cqt_kernel_sender, cqt_kernel_receiver) = mpsc::channel();
         SpectrumAnalyzer {
-            params: Arc::new(SpectrumAnalyzerParams::default())
+            params: Arc::new(SpectrumAnalyzerParams::default()),
+            analyzer: Analyzer::new(44100.0),
+            reference_analyzer: Analyzer::new(44100.0),
+            results_input,
+            results_output: Arc::new(Mutex::new(results_output)),
+            held_notes: Arc::new(HeldNotes::default()),
+            last_peak_cc: None,
+            cqt_kernel_sender,
+            cqt_kernel_receiver,
+            cqt_enabled: false,
+            cqt_kernel_pending: false,
         }
     }
 }
 
+impl SpectrumAnalyzer {
+    /// Capture the magnitudes of every channel of the latest results as the reference curve,
+    /// replacing the previous one. The results of the sidechain's reference signal are not part
+    /// of the curve. The curve is saved with the project.
+    pub fn capture_reference(&self) {
+        capture_reference(
+            &self.results_output,
+            &self.params.reference_curve,
+            self.analyzer.fft_size(),
+        );
+    }
+
+    /// Get the captured reference curve, resampled to the current FFT size if it was captured
+    /// at a different one, or `None` if no curve has been captured.
+    pub fn reference_curve(&self) -> Option<ReferenceCurve> {
+        let encoded = self.params.reference_curve.read().unwrap_or_else(PoisonError::into_inner);
+        ReferenceCurve::decode(&encoded).map(|curve| curve.resampled(self.analyzer.fft_size()))
+    }
+
+    /// Push the current parameter values into both analyzers.
+    fn apply_params(&mut self) {
+        for analyzer in [&mut self.analyzer, &mut self.reference_analyzer] {
+            analyzer.set_fft_size(self.params.fft_size.value().samples());
+            analyzer.set_window(self.params.window.value());
+            analyzer.set_smoothing(self.params.smoothing.value());
+            analyzer.set_slope_db_per_octave(self.params.slope.value());
+            analyzer.set_frozen(self.params.freeze.value());
+        }
+    }
+
+    /// Get the editor's side of the channel the latest results are published to. Only the
+    /// editor locks this mutex, the audio thread writes to its side of the channel without
+    /// locking.
+    pub fn results(&self) -> Arc<Mutex<ResultsOutput>> {
+        self.results_output.clone()
+    }
+
+    /// Install the newest kernel a [`AnalyzerTask::RecomputeCqtKernel`] task has finished in the
+    /// analyzer, and get the kernel the analyzer uses. This doesn't wait for a task that's still
+    /// running.
+    pub fn update_cqt_kernel(&mut self) -> Option<&CqtKernel> {
+        if let Some(kernel) = self.cqt_kernel_receiver.try_iter().last() {
+            self.analyzer.set_cqt_kernel(kernel);
+            self.cqt_kernel_pending = false;
+        }
+
+        self.analyzer.cqt_kernel()
+    }
+
+    /// Set whether the constant-Q transform is shown. While it is, the audio thread starts a
+    /// [`AnalyzerTask::RecomputeCqtKernel`] task whenever the analyzer's kernel doesn't match the
+    /// sample rate, so [`Analyzer::process_cqt()`] never has to compute it itself.
+    pub fn set_cqt_enabled(&mut self, enabled: bool) {
+        self.cqt_enabled = enabled;
+    }
+
+    /// Get the MIDI notes that are held, so the editor can draw markers at their fundamentals and
+    /// harmonics with [`HeldNotes::markers()`].
+    pub fn held_notes(&self) -> Arc<HeldNotes> {
+        self.held_notes.clone()
+    }
+}
+
+/// Capture the magnitudes of every channel of the latest results in `results`, which were
+/// analyzed at `fft_size`, and store the encoded curve in `reference_curve`.
+fn capture_reference(
+    results: &Mutex<ResultsOutput>,
+    reference_curve: &RwLock<String>,
+    fft_size: usize,
+) {
+    let mut results = results.lock().unwrap_or_else(PoisonError::into_inner);
+    let curve = ReferenceCurve {
+        fft_size,
+        channels: results
+            .read()
+            .iter()
+            .filter(|result| result.label.as_deref() != Some(REFERENCE_LABEL))
+            .map(|result| result.magnitudes.clone())
+            .collect(),
+    };
+
+    *reference_curve.write().unwrap_or_else(PoisonError::into_inner) = curve.encode();
+}
+
+/// Get the status [`SpectrumAnalyzer::process()`] returns after `analyzer` analyzed a block. The
+/// smoothing makes the display decay after the input goes silent, so the host is asked to keep
+/// processing for the samples of [`Analyzer::smoothing_decay_samples()`], or indefinitely if the
+/// display never decays. Once the silent blocks have decayed the display, or if it never decays,
+/// there is nothing left to draw and the host may suspend the plugin.
+pub fn process_status(analyzer: &Analyzer) -> ProcessStatus {
+    let decay_frames = analyzer.smoothing_decay_frames();
+    let silent_blocks = analyzer.silent_blocks();
+    if silent_blocks > 0 && silent_blocks >= decay_frames.unwrap_or(0) {
+        return ProcessStatus::Normal;
+    }
+
+    match analyzer.smoothing_decay_samples() {
+        Some(0) => ProcessStatus::Normal,
+        Some(samples) => ProcessStatus::Tail(u32::try_from(samples).unwrap_or(u32::MAX)),
+        None => ProcessStatus::KeepAlive,
+    }
+}
+
+/// The names hosts show for a layout and its ports. The main ports are named after what the
+/// plugin does with them, as the audio only passes through and the input is what gets analyzed.
+const fn port_names(layout: &'static str, aux_inputs: &'static [&'static str]) -> PortNames {
+    PortNames {
+        layout: Some(layout),
+        main_input: Some("Analyzed Input"),
+        main_output: Some("Pass-Through Output"),
+        aux_inputs,
+        aux_outputs: &[],
+    }
+}
+
 impl Plugin for SpectrumAnalyzer {
     const NAME: &'static str = "Apollo Spectrum Analyzer";
     const VENDOR: &'static str = "Apollo Digital Audio Workbench";
@@ -36,18 +357,43 @@ impl Plugin for SpectrumAnalyzer {
         AudioIOLayout {
             main_input_channels: NonZeroU32::new(2),
             main_output_channels: NonZeroU32::new(2),
+            names: port_names("Stereo", &[]),
             ..AudioIOLayout::const_default()
         },
         AudioIOLayout {
             main_input_channels: NonZeroU32::new(1),
             main_output_channels: NonZeroU32::new(1),
+            names: port_names("Mono", &[]),
+            ..AudioIOLayout::const_default()
+        },
+        // Stereo with a stereo reference signal on the sidechain, to compare the two spectra.
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(2),
+            main_output_channels: NonZeroU32::new(2),
+            aux_input_ports: &[new_nonzero_u32(2)],
+            names: port_names("Stereo with Reference", &[REFERENCE_LABEL]),
+            ..AudioIOLayout::const_default()
+        },
+        // 5.1 and 7.1 surround. Every channel is analyzed on its own.
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(6),
+            main_output_channels: NonZeroU32::new(6),
+            names: port_names("5.1 Surround", &[]),
+            ..AudioIOLayout::const_default()
+        },
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(8),
+            main_output_channels: NonZeroU32::new(8),
+            names: port_names("7.1 Surround", &[]),
             ..AudioIOLayout::const_default()
         },
     ];
+    const MIDI_INPUT: MidiConfig = MidiConfig::Basic;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::MidiCCs;
     const SAMPLE_ACCURATE_AUTOMATION: bool = true;
 
     type SysExMessage = ();
-    type BackgroundTask = ();
+    type BackgroundTask = AnalyzerTask;
 
     /// Get the parameters of the plugin. This will be a clone of the parameters that the plugin
     /// uses.
@@ -55,36 +401,252 @@ impl Plugin for SpectrumAnalyzer {
         self.params.clone()
     }
 
+    /// Repair a state before it's loaded. The state is first migrated from the version that
+    /// saved it with [`state::migrate()`]. Parameters this build doesn't have are then dropped,
+    /// and parameters missing from the state get their default values. Finally, values a host or
+    /// an older build may have stored that this build doesn't support are replaced: an FFT size
+    /// that isn't one of the choices, which older builds could have stored as a plain number,
+    /// becomes the nearest supported size, a window stored as a number is looked up by its
+    /// [`WindowFunction::from_id()`], an unknown window becomes the default window, and out of
+    /// range smoothing, slope and peak CC values are clamped.
+    fn filter_state(state: &mut PluginState) {
+        state::migrate(state);
+        let defaults = SpectrumAnalyzerParams::default();
+
+        // A state saved by a different build may have parameters this build doesn't know, which
+        // are dropped, and may lack some of this build's parameters, which get their defaults.
+        let default_values = defaults.default_values();
+        state.params.retain(|id, _| {
+            let known = default_values.contains_key(id);
+            if !known {
+                nih_debug_assert_failure!("Dropping unknown parameter '{id}' from the saved state");
+            }
+
+            known
+        });
+        for (id, value) in default_values {
+            state.params.entry(id).or_insert(value);
+        }
+
+        if let Some(value) = state.params.get_mut("fft_size") {
+            let samples = match value {
+                ParamValue::String(id) => id.parse().ok(),
+                ParamValue::I32(samples) => usize::try_from(*samples).ok(),
+                _ => None,
+            };
+            let fft_size =
+                samples.map_or(defaults.fft_size.default_plain_value(), FftSize::nearest);
+            *value = ParamValue::String(fft_size.samples().to_string());
+        }
+
+        if let Some(value) = state.params.get_mut("window") {
+            let window = match value {
+                ParamValue::String(id) => WindowFunction::ids()
+                    .and_then(|ids| ids.iter().position(|known| known == id))
+                    .map(WindowFunction::from_index),
+                ParamValue::I32(id) => u32::try_from(*id).ok().and_then(WindowFunction::from_id),
+                _ => None,
+            };
+            let window = window.unwrap_or(defaults.window.default_plain_value());
+            *value = ParamValue::String(window_id(window));
+        }
+
+        let ranges = [
+            ("smoothing", &defaults.smoothing, 0.0, 1.0),
+            ("slope", &defaults.slope, -MAX_SLOPE_DB_PER_OCTAVE, MAX_SLOPE_DB_PER_OCTAVE),
+        ];
+        for (id, param, min, max) in ranges {
+            if let Some(value) = state.params.get_mut(id) {
+                *value = match value {
+                    ParamValue::F32(value) if value.is_finite() => {
+                        ParamValue::F32(value.clamp(min, max))
+                    }
+                    _ => ParamValue::F32(param.default_plain_value()),
+                };
+            }
+        }
+
+        if let Some(value) = state.params.get_mut("peak_cc") {
+            *value = match value {
+                ParamValue::I32(cc) => ParamValue::I32((*cc).clamp(0, MAX_CC)),
+                _ => ParamValue::I32(defaults.peak_cc.default_plain_value()),
+            };
+        }
+    }
+
+    /// Get the function that runs the plugin's background tasks. The results are sent back to the
+    /// plugin, which picks them up with [`SpectrumAnalyzer::update_cqt_kernel()`].
+    fn task_executor(&mut self) -> TaskExecutor<Self> {
+        let cqt_kernel_sender = self.cqt_kernel_sender.clone();
+        let results_output = self.results_output.clone();
+        let reference_curve = self.params.reference_curve.clone();
+        Box::new(move |task| match task {
+            AnalyzerTask::RecomputeCqtKernel { sample_rate, bins_per_octave, f_min } => {
+                let kernel =
+                    CqtKernel::new(&mut FftPlanner::new(), sample_rate, bins_per_octave, f_min);
+                // The plugin is gone if nobody receives the kernel, so it's fine to drop it.
+                let _ = cqt_kernel_sender.send(kernel);
+            }
+            AnalyzerTask::CaptureReference { fft_size } => {
+                capture_reference(&results_output, &reference_curve, fft_size);
+            }
+        })
+    }
+
     /// Get the editor of the plugin. This is called when the host wants to open the editor of the
     /// plugin. If the plugin does not have an editor, it should return `None`.
     fn editor(
         &mut self,
-        _async_executor: AsyncExecutor<Self>,
+        async_executor: AsyncExecutor<Self>,
     ) -> Option<Box<dyn Editor>> {
-        None
+        // The editor doesn't know about the offline profile, but it's only used to look at the
+        // spectrum while playing back, when the FFT size parameter is the size that's analyzed.
+        let params = self.params.clone();
+        let capture_reference = move || {
+            let fft_size = params.fft_size.value().samples();
+            async_executor.execute_background(AnalyzerTask::CaptureReference { fft_size });
+        };
+
+        editor::create(
+            self.params.editor_state.clone(),
+            self.results_output.clone(),
+            self.held_notes.clone(),
+            capture_reference,
+        )
     }
 
     /// Initialize the plugin. This is called when the plugin is loaded. The plugin should return
     /// `true` if initialization was successful, and `false` otherwise.
     fn initialize(
         &mut self,
-        _audio_io_layout: &AudioIOLayout,
+        audio_io_layout: &AudioIOLayout,
         buffer_config: &BufferConfig,
-        _context: &mut impl InitContext<Self>,
+        context: &mut impl InitContext<Self>,
     ) -> bool {
+        // There is nothing to analyze without a main input, so the host is told setup failed
+        // instead of running the plugin with analyzers that were sized for no channels.
+        let Some(channel_count) = audio_io_layout.main_input_channels.map(NonZeroU32::get) else {
+            nih_debug_assert_failure!("The audio IO layout has no main input");
+            return false;
+        };
+
+        // Everything the analyzers need is allocated here, as the audio thread must not allocate.
+        // The per-channel state is sized for the negotiated layout, which can differ from the
+        // layout of an earlier activation.
+        let reference_count = audio_io_layout.aux_input_ports.first().map_or(0, |c| c.get());
+        for analyzer in [&mut self.analyzer, &mut self.reference_analyzer] {
+            analyzer.set_sample_rate(buffer_config.sample_rate);
+            // Bounces get the larger frames of the offline profile, and reactivating for
+            // realtime processing switches back.
+            analyzer.set_offline(buffer_config.process_mode == ProcessMode::Offline);
+            analyzer.set_silence_threshold(SILENCE_THRESHOLD_DB);
+        }
+        self.apply_params();
+        let max_block_size = buffer_config.max_buffer_size as usize;
+        self.analyzer.prepare(channel_count as usize, max_block_size);
+        self.reference_analyzer.prepare(reference_count as usize, max_block_size);
+        // The FFT size can be changed while processing, so every choice is planned here and
+        // switching only selects one of the plans.
+        let fft_sizes = FftSize::ALL.map(FftSize::samples);
+        self.analyzer.prepare_fft_sizes(channel_count as usize, &fft_sizes);
+        self.reference_analyzer.prepare_fft_sizes(reference_count as usize, &fft_sizes);
+        // Nothing from before the plugin was last deactivated should show up in the new session.
+        self.analyzer.reset();
+        self.reference_analyzer.reset();
+
+        let bin_count = self.analyzer.max_bin_count(channel_count.max(reference_count) as usize);
+        let (results_input, results_output) =
+            results_channel(channel_count as usize, reference_count as usize, bin_count);
+        self.results_input = results_input;
+        *self.results_output.lock().unwrap_or_else(PoisonError::into_inner) = results_output;
+        self.held_notes.clear();
+        self.last_peak_cc = None;
+
+        // The audio passes through the main analyzer, the reference signal only goes into its
+        // analyzer.
+        context.set_latency_samples(self.analyzer.latency_samples());
+
         true
     }
 
+    /// Reset the plugin's state. This is called when the host relocates the playhead or loops,
+    /// so the smoothing and everything else the analyzer carries over between blocks is cleared,
+    /// and the next results only reflect the audio from there on.
+    fn reset(&mut self) {
+        self.analyzer.reset();
+        self.reference_analyzer.reset();
+    }
+
     /// Process audio. This is called for each block of audio that the plugin processes.
     /// The plugin should return [`ProcessStatus::Normal`] if processing was successful, and
     /// [`ProcessStatus::Error`] if not. See [`ProcessStatus`] for other possible return values.
     fn process(
         &mut self,
         buffer: &mut Buffer,
-        _aux: &mut AuxiliaryBuffers,
-        _context: &mut impl ProcessContext<Self>,
+        aux: &mut AuxiliaryBuffers,
+        context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
-        ProcessStatus::Normal
+        self.apply_params();
+
+        // The constant-Q transform kernel depends on the sample rate, and takes too long to
+        // compute on the audio thread. It's only needed while the transform is shown.
+        let sample_rate = self.analyzer.sample_rate();
+        let kernel_is_current = self
+            .update_cqt_kernel()
+            .is_some_and(|kernel| kernel.matches(sample_rate, CQT_BINS_PER_OCTAVE, CQT_F_MIN));
+        if self.cqt_enabled && !kernel_is_current && !self.cqt_kernel_pending {
+            context.execute_background(AnalyzerTask::RecomputeCqtKernel {
+                sample_rate,
+                bins_per_octave: CQT_BINS_PER_OCTAVE,
+                f_min: CQT_F_MIN,
+            });
+            self.cqt_kernel_pending = true;
+        }
+
+        // The markers are drawn per frame, so the timing of the note events within the block
+        // doesn't matter.
+        while let Some(event) = context.next_event() {
+            match event {
+                NoteEvent::NoteOn { note, .. } => self.held_notes.note_on(note),
+                NoteEvent::NoteOff { note, .. } | NoteEvent::Choke { note, .. } => {
+                    self.held_notes.note_off(note)
+                }
+                _ => (),
+            }
+        }
+
+        // The audio passes through unchanged, the analyzers only read it. The sidechain has its
+        // own analyzer, so its buffer doesn't need to match the main buffer's length.
+        let results = self.analyzer.process(buffer);
+        let reference = match aux.inputs.first_mut() {
+            Some(reference) => self.reference_analyzer.process(reference),
+            None => &[],
+        };
+        if !results.is_empty() {
+            self.results_input.publish(results, reference);
+
+            // The strongest peak of all channels is sent as a CC, so it can be mapped to a
+            // parameter of another plugin. Hosts only need to hear about it when it changes.
+            let peak = results
+                .iter()
+                .filter_map(Analyzer::dominant_peak)
+                .max_by(|(_, a), (_, b)| a.total_cmp(b));
+            if let Some((frequency, _)) = peak {
+                let peak_cc = (self.params.peak_cc.value() as u8, frequency_to_cc(frequency));
+                if self.last_peak_cc != Some(peak_cc) {
+                    let (cc, value) = peak_cc;
+                    context.send_event(NoteEvent::MidiCC {
+                        timing: 0,
+                        channel: 0,
+                        cc,
+                        value: value as f32 / 127.0,
+                    });
+                    self.last_peak_cc = Some(peak_cc);
+                }
+            }
+        }
+
+        process_status(&self.analyzer)
     }
 }
 
@@ -102,5 +664,21 @@ impl Vst3Plugin for SpectrumAnalyzer {
     ];
 }
 
+impl ClapPlugin for SpectrumAnalyzer {
+    const CLAP_ID: &'static str = "com.apollo-daw.spectrum-analyzer";
+    const CLAP_DESCRIPTION: Option<&'static str> = Some("A real-time spectrum analyzer");
+    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+    // These match the VST3 subcategories, plus the channel configurations of the layouts.
+    const CLAP_FEATURES: &'static [ClapFeature] = &[
+        ClapFeature::AudioEffect,
+        ClapFeature::Analyzer,
+        ClapFeature::Stereo,
+        ClapFeature::Mono,
+        ClapFeature::Surround,
+    ];
+}
+
+nih_export_clap!(SpectrumAnalyzer);
 nih_export_vst3!(SpectrumAnalyzer);
 
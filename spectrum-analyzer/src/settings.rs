@@ -0,0 +1,78 @@
+use std::ops::{Deref, DerefMut};
+use crate::analyzer::{Analyzer, ChannelMode, MagnitudeScale};
+use crate::config::AnalyzerConfig;
+
+/// A snapshot of every setting of an [`Analyzer`], as taken by [`Analyzer::settings()`]. The
+/// sample rate isn't part of it, as that's up to the host. Every field has the meaning of the
+/// analyzer's setter of the same name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalyzerSettings {
+    /// The configuration of all channels without an override. Its FFT size is the one outside
+    /// of the offline profile.
+    pub config: AnalyzerConfig,
+    pub offline: bool,
+    /// The configuration override of every channel, if it has one.
+    pub channel_overrides: Vec<Option<AnalyzerConfig>>,
+    pub channel_mode: ChannelMode,
+    pub frequency_range: Option<(f32, f32)>,
+    pub overlap: f32,
+    pub pending_capacity: Option<usize>,
+    pub prefilter_cutoff: f32,
+    pub phase_output: bool,
+    pub phase_smoothing: f32,
+    pub smoothing: f32,
+    /// The time constant the smoothing is derived from, which takes precedence over
+    /// [`Self::smoothing`] if it's set.
+    pub smoothing_time: Option<f32>,
+    pub slope_db_per_octave: f32,
+    pub slope_reference: f32,
+    pub slope_fit_range: (f32, f32),
+    pub magnitude_scale: MagnitudeScale,
+    pub floor_db: f32,
+    pub reference_channel: Option<usize>,
+    pub include_nyquist: bool,
+    pub smart_resolution: Option<f32>,
+    pub multiresolution: bool,
+    pub amplitude_correction: bool,
+    pub silence_threshold: f32,
+    pub frozen: bool,
+}
+
+/// Keeps temporary settings applied to an [`Analyzer`], and restores the settings from before
+/// when it's dropped, see [`Analyzer::with_settings()`]. The analyzer is used through the guard
+/// in the meantime.
+pub struct SettingsGuard<'a> {
+    analyzer: &'a mut Analyzer,
+    previous: AnalyzerSettings,
+}
+
+impl<'a> SettingsGuard<'a> {
+    pub(crate) fn new(analyzer: &'a mut Analyzer, previous: AnalyzerSettings) -> Self {
+        SettingsGuard { analyzer, previous }
+    }
+}
+
+impl Deref for SettingsGuard<'_> {
+    type Target = Analyzer;
+
+    fn deref(&self) -> &Analyzer {
+        self.analyzer
+    }
+}
+
+impl DerefMut for SettingsGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Analyzer {
+        self.analyzer
+    }
+}
+
+impl Drop for SettingsGuard<'_> {
+    fn drop(&mut self) {
+        // The previous settings were in use, so they can only be rejected if the sample rate was
+        // lowered below their frequency range in the meantime. The rest is restored regardless.
+        if self.analyzer.apply_settings(&self.previous).is_err() {
+            self.previous.frequency_range = None;
+            let _ = self.analyzer.apply_settings(&self.previous);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use nih_plug::prelude::PluginState;
+
+/// A step that upgrades a state saved by a build older than `version`.
+struct Migration {
+    version: (u32, u32, u32),
+    apply: fn(&mut PluginState),
+}
+
+/// Every migration, ordered by the version that introduced the change it migrates.
+const MIGRATIONS: &[Migration] = &[Migration { version: (0, 1, 0), apply: rename_fft_param }];
+
+/// Upgrade a state saved by an older build to the current version, by applying every migration
+/// that was introduced after the build that saved it, in order. A state without a readable
+/// version is treated as the oldest possible state. A state saved by a newer build is left
+/// untouched, as this build can't know what changed since.
+pub fn migrate(state: &mut PluginState) {
+    let current = parse_version(env!("CARGO_PKG_VERSION"));
+    let from = parse_version(&state.version);
+    if from > current {
+        return;
+    }
+
+    for migration in MIGRATIONS.iter().filter(|migration| from < migration.version) {
+        (migration.apply)(state);
+    }
+    state.version = String::from(env!("CARGO_PKG_VERSION"));
+}
+
+/// Parse a `major.minor.patch` version. Missing or unreadable components count as zero, and
+/// anything after the digits of a component, like a `-beta` suffix, is ignored.
+fn parse_version(version: &str) -> (u32, u32, u32) {
+    let mut components = version.trim().split('.').map(|component| {
+        let digits = component.chars().take_while(char::is_ascii_digit).collect::<String>();
+        digits.parse().unwrap_or(0)
+    });
+
+    (
+        components.next().unwrap_or(0),
+        components.next().unwrap_or(0),
+        components.next().unwrap_or(0),
+    )
+}
+
+/// The FFT size parameter used to be called `fft`.
+fn rename_fft_param(state: &mut PluginState) {
+    if let Some(value) = state.params.remove("fft") {
+        state.params.entry(String::from("fft_size")).or_insert(value);
+    }
+}
+
+/// A snapshot of the magnitudes of every channel, saved with the project so the live spectrum
+/// can be compared against it later. The FFT size it was captured at is stored along with it, so
+/// it can be resampled when the current FFT size is different.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReferenceCurve {
+    /// The FFT size the magnitudes were captured at.
+    pub fft_size: usize,
+    /// The magnitudes of every bin, for every channel.
+    pub channels: Vec<Vec<f32>>,
+}
+
+impl ReferenceCurve {
+    /// Encode the curve as the FFT size followed by the comma separated magnitudes of every
+    /// channel, all separated by semicolons.
+    pub fn encode(&self) -> String {
+        let mut encoded = self.fft_size.to_string();
+        for magnitudes in &self.channels {
+            encoded.push(';');
+            let values = magnitudes.iter().map(f32::to_string).collect::<Vec<_>>();
+            encoded.push_str(&values.join(","));
+        }
+
+        encoded
+    }
+
+    /// Decode a curve encoded by [`ReferenceCurve::encode()`], or return `None` if it's not a
+    /// valid encoding.
+    pub fn decode(encoded: &str) -> Option<ReferenceCurve> {
+        let mut parts = encoded.split(';');
+        let fft_size = parts.next()?.parse().ok()?;
+        let channels = parts
+            .map(|channel| {
+                channel
+                    .split(',')
+                    .filter(|value| !value.is_empty())
+                    .map(|value| value.parse().ok())
+                    .collect()
+            })
+            .collect::<Option<_>>()?;
+
+        Some(ReferenceCurve { fft_size, channels })
+    }
+
+    /// Resample the curve to the bins of another FFT size at the same sample rate, by linearly
+    /// interpolating between the two captured bins around every new bin's frequency. Bins above
+    /// the captured range take the value of the last captured bin.
+    pub fn resampled(&self, fft_size: usize) -> ReferenceCurve {
+        if fft_size == self.fft_size || self.fft_size == 0 {
+            return self.clone();
+        }
+
+        let ratio = self.fft_size as f32 / fft_size as f32;
+        let channels = self
+            .channels
+            .iter()
+            .map(|magnitudes| {
+                let bin_count = magnitudes.len() * fft_size / self.fft_size;
+                (0..bin_count)
+                    .map(|bin| {
+                        let position = bin as f32 * ratio;
+                        let lower = (position as usize).min(magnitudes.len() - 1);
+                        let upper = (lower + 1).min(magnitudes.len() - 1);
+                        let fraction = position - lower as f32;
+                        magnitudes[lower] + (magnitudes[upper] - magnitudes[lower]) * fraction
+                    })
+                    .collect()
+            })
+            .collect();
+
+        ReferenceCurve { fft_size, channels }
+    }
+}
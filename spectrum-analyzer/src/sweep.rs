@@ -0,0 +1,172 @@
+use std::f32::consts::PI;
+use rustfft::FftPlanner;
+use rustfft::num_complex::Complex;
+
+/// An exponential (logarithmic) sine sweep, used as the excitation signal for impulse response
+/// measurements. Because the frequency rises exponentially, the sweep spends the same time in
+/// every octave, and harmonic distortion products end up before the linear impulse response after
+/// deconvolution, where they are easy to cut off.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExponentialSweep {
+    /// The frequency in Hz the sweep starts at.
+    pub start_frequency: f32,
+    /// The frequency in Hz the sweep ends at.
+    pub end_frequency: f32,
+    /// The length of the sweep in seconds.
+    pub duration: f32,
+}
+
+/// The result of an impulse response measurement.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImpulseResponse {
+    /// The linear impulse response, starting at a delay of zero samples.
+    pub samples: Vec<f32>,
+    /// The center frequency in Hz of every bin of the frequency response.
+    pub frequencies: Vec<f32>,
+    /// The linear magnitude of the frequency response, which is `1.0` where the measured system
+    /// passes the signal unchanged.
+    pub magnitudes: Vec<f32>,
+}
+
+/// The state of an impulse response capture. The sweep is played back block by block while the
+/// response of the measured system is recorded.
+pub(crate) struct SweepCapture {
+    sweep: Vec<f32>,
+    inverse_filter: Vec<f32>,
+    /// The number of sweep samples that have been played back.
+    position: usize,
+    recording: Vec<f32>,
+}
+
+impl Default for ExponentialSweep {
+    /// Create a new instance of [`ExponentialSweep`] with defaults. This sweeps the audible range
+    /// from 20 Hz to 20 kHz in five seconds.
+    fn default() -> Self {
+        ExponentialSweep {
+            start_frequency: 20.0,
+            end_frequency: 20_000.0,
+            duration: 5.0,
+        }
+    }
+}
+
+impl ExponentialSweep {
+    /// Generate the samples of the sweep at the given sample rate. This follows Farina's
+    /// definition `x(t) = sin(2π f1 T / R (e^(t R / T) - 1))` with `R = ln(f2 / f1)`.
+    pub fn generate(&self, sample_rate: f32) -> Vec<f32> {
+        let length = (self.duration * sample_rate) as usize;
+        let rate = (self.end_frequency / self.start_frequency).ln();
+        let scale = 2.0 * PI * self.start_frequency * self.duration / rate;
+        (0..length)
+            .map(|i| {
+                let t = i as f32 / sample_rate;
+                (scale * ((t * rate / self.duration).exp() - 1.0)).sin()
+            })
+            .collect()
+    }
+
+    /// Generate the inverse filter of the sweep, which is the time-reversed sweep with an
+    /// envelope that falls by 6 dB per octave. The envelope compensates for the sweep's pink
+    /// spectrum, so that convolving the sweep with its inverse filter gives a band-limited pulse.
+    pub fn inverse_filter(&self, sample_rate: f32) -> Vec<f32> {
+        let sweep = self.generate(sample_rate);
+        let rate = (self.end_frequency / self.start_frequency).ln();
+        let length = sweep.len() as f32;
+        sweep
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, sample)| sample * (-(i as f32) * rate / length).exp())
+            .collect()
+    }
+}
+
+impl SweepCapture {
+    /// Start a new capture of the given sweep.
+    pub(crate) fn new(sweep: &ExponentialSweep, sample_rate: f32) -> Self {
+        SweepCapture {
+            sweep: sweep.generate(sample_rate),
+            inverse_filter: sweep.inverse_filter(sample_rate),
+            position: 0,
+            recording: Vec::new(),
+        }
+    }
+
+    /// Write the next samples of the sweep to `output`. Once the whole sweep has been played
+    /// back the output is silent, so the tail of the measured system can be recorded.
+    pub(crate) fn play(&mut self, output: &mut [f32]) {
+        for sample in output {
+            *sample = self.sweep.get(self.position).copied().unwrap_or(0.0);
+            self.position += 1;
+        }
+    }
+
+    /// Append the response of the measured system to the recording.
+    pub(crate) fn record(&mut self, input: &[f32]) {
+        self.recording.extend_from_slice(input);
+    }
+
+    /// Deconvolve the recording by convolving it with the sweep's inverse filter. The linear
+    /// impulse response starts one sweep length into the convolution, anything before that is
+    /// harmonic distortion and is discarded. The result is normalized by deconvolving the sweep
+    /// itself, so a system that passes the sweep unchanged has a unit impulse.
+    pub(crate) fn impulse_response(
+        &self,
+        fft_planner: &mut FftPlanner<f32>,
+        sample_rate: f32,
+    ) -> ImpulseResponse {
+        if self.recording.is_empty() || self.sweep.is_empty() {
+            return ImpulseResponse::default();
+        }
+
+        let fft_size = (self.recording.len() + self.inverse_filter.len()).next_power_of_two();
+        let forward = fft_planner.plan_fft_forward(fft_size);
+        let inverse = fft_planner.plan_fft_inverse(fft_size);
+        let transform = |signal: &[f32]| {
+            let mut spectrum = vec![Complex::new(0.0, 0.0); fft_size];
+            for (bin, &sample) in spectrum.iter_mut().zip(signal) {
+                *bin = Complex::new(sample, 0.0);
+            }
+            forward.process(&mut spectrum);
+            spectrum
+        };
+
+        let inverse_spectrum = transform(&self.inverse_filter);
+        let mut response = transform(&self.recording);
+        let mut reference = transform(&self.sweep);
+        for ((response, reference), inverse_bin) in
+            response.iter_mut().zip(reference.iter_mut()).zip(&inverse_spectrum)
+        {
+            *response *= *inverse_bin;
+            *reference *= *inverse_bin;
+        }
+        inverse.process(&mut response);
+        inverse.process(&mut reference);
+
+        let peak = reference.iter().map(|bin| bin.re.abs()).fold(0.0, f32::max);
+        if peak == 0.0 {
+            return ImpulseResponse::default();
+        }
+
+        let start = self.sweep.len() - 1;
+        let samples = response[start..start + self.recording.len()]
+            .iter()
+            .map(|bin| bin.re / peak)
+            .collect::<Vec<_>>();
+
+        let fft_size = samples.len().next_power_of_two();
+        let fft = fft_planner.plan_fft_forward(fft_size);
+        let mut spectrum = vec![Complex::new(0.0, 0.0); fft_size];
+        for (bin, &sample) in spectrum.iter_mut().zip(&samples) {
+            *bin = Complex::new(sample, 0.0);
+        }
+        fft.process(&mut spectrum);
+
+        let frequencies = (0..fft_size / 2)
+            .map(|i| i as f32 * sample_rate / fft_size as f32)
+            .collect();
+        let magnitudes = spectrum[..fft_size / 2].iter().map(|bin| bin.norm()).collect();
+
+        ImpulseResponse { samples, frequencies, magnitudes }
+    }
+}
@@ -0,0 +1,46 @@
+/// Get `len` samples of a full-scale sine at `frequency` Hz, starting at phase zero.
+pub fn sine(frequency: f32, sample_rate: f32, len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|i| (2.0 * std::f32::consts::PI * frequency * i as f32 / sample_rate).sin())
+        .collect()
+}
+
+/// Get `len` samples of white noise, uniformly distributed in `[-1, 1)`.
+pub fn white_noise(seed: u32, len: usize) -> Vec<f32> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            // A linear congruential generator is plenty random for test signals, and gives the
+            // same sequence on every platform.
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 8) as f32 / (1 << 23) as f32 - 1.0
+        })
+        .collect()
+}
+
+/// Get `len` samples of pink noise, whose power falls by 3 dB per octave, made by filtering the
+/// white noise of [`white_noise()`] with Paul Kellet's economy filter. The samples stay within
+/// about `[-1, 1]`.
+pub fn pink_noise(seed: u32, len: usize) -> Vec<f32> {
+    let (mut b0, mut b1, mut b2) = (0.0, 0.0, 0.0);
+    white_noise(seed, len)
+        .into_iter()
+        .map(|white| {
+            b0 = 0.99765 * b0 + white * 0.0990460;
+            b1 = 0.96300 * b1 + white * 0.2965164;
+            b2 = 0.57000 * b2 + white * 1.0526913;
+            (b0 + b1 + b2 + white * 0.1848) * 0.25
+        })
+        .collect()
+}
+
+/// Get `len` samples that are all zero except for a one at `position`. A position past the end
+/// gives only zeros.
+pub fn impulse(len: usize, position: usize) -> Vec<f32> {
+    let mut samples = vec![0.0; len];
+    if let Some(sample) = samples.get_mut(position) {
+        *sample = 1.0;
+    }
+
+    samples
+}
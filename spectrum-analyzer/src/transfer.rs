@@ -0,0 +1,100 @@
+use rustfft::num_complex::Complex;
+
+/// The frequency response between a reference signal and the signal it's measured at, as
+/// computed by [`Analyzer::transfer_function()`](crate::analyzer::Analyzer::transfer_function()).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransferResult {
+    /// The center frequency in Hz of every bin.
+    pub frequencies: Vec<f32>,
+    /// The gain of every bin in dB. This is negative infinity for bins the reference has no
+    /// energy in.
+    pub magnitude_db: Vec<f32>,
+    /// The phase shift of every bin in degrees, between -180 and 180.
+    pub phase_deg: Vec<f32>,
+    /// The magnitude-squared coherence of every bin, between 0 and 1. This is close to one
+    /// where the measured signal is a linear function of the reference, and drops where noise
+    /// or other signals dominate, so it tells how much the other values of a bin can be trusted.
+    pub coherence: Vec<f32>,
+}
+
+/// The auto- and cross-spectra of the frames a transfer function is averaged over, summed per
+/// bin. The sums don't have to be divided by the number of frames, as the transfer function
+/// and the coherence are ratios of them.
+pub(crate) struct TransferAverage {
+    /// The FFT size the spectra were computed with.
+    fft_size: usize,
+    /// The spectrum of the reference's current frame, until the measured frame it belongs to
+    /// is accumulated.
+    reference: Vec<Complex<f32>>,
+    /// The power of the reference.
+    reference_power: Vec<f32>,
+    /// The power of the measured signal.
+    measured_power: Vec<f32>,
+    /// The cross-spectrum of the reference and the measured signal.
+    cross: Vec<Complex<f32>>,
+}
+
+impl TransferAverage {
+    /// Create an empty average for spectra of `fft_size` samples.
+    pub(crate) fn new(fft_size: usize) -> Self {
+        let bin_count = fft_size / 2 + 1;
+        TransferAverage {
+            fft_size,
+            reference: vec![Complex::new(0.0, 0.0); bin_count],
+            reference_power: vec![0.0; bin_count],
+            measured_power: vec![0.0; bin_count],
+            cross: vec![Complex::new(0.0, 0.0); bin_count],
+        }
+    }
+
+    /// Get the FFT size the spectra were computed with.
+    pub(crate) fn fft_size(&self) -> usize {
+        self.fft_size
+    }
+
+    /// Set the full spectrum of the reference's current frame.
+    pub(crate) fn set_reference(&mut self, spectrum: &[Complex<f32>]) {
+        let bin_count = self.reference.len();
+        self.reference.copy_from_slice(&spectrum[..bin_count]);
+    }
+
+    /// Add the full spectrum of the measured signal's current frame, and the reference frame
+    /// that was set before it, to the average.
+    pub(crate) fn accumulate(&mut self, spectrum: &[Complex<f32>]) {
+        for (bin, &reference) in self.reference.iter().enumerate() {
+            let measured = spectrum[bin];
+            self.reference_power[bin] += reference.norm_sqr();
+            self.measured_power[bin] += measured.norm_sqr();
+            self.cross[bin] += reference.conj() * measured;
+        }
+    }
+
+    /// Compute the transfer function `H(f) = Sxy / Sxx` and the coherence
+    /// `|Sxy|² / (Sxx · Syy)` of every bin, where `x` is the reference and `y` the measured
+    /// signal.
+    pub(crate) fn result(&self, sample_rate: f32) -> TransferResult {
+        let bin_width = sample_rate / self.fft_size as f32;
+        let mut result = TransferResult::default();
+        for (bin, &cross) in self.cross.iter().enumerate() {
+            let (reference_power, measured_power) =
+                (self.reference_power[bin], self.measured_power[bin]);
+            result.frequencies.push(bin as f32 * bin_width);
+            if reference_power > 0.0 {
+                let transfer = cross / reference_power;
+                result.magnitude_db.push(20.0 * transfer.norm().log10());
+                result.phase_deg.push(transfer.arg().to_degrees());
+            } else {
+                result.magnitude_db.push(f32::NEG_INFINITY);
+                result.phase_deg.push(0.0);
+            }
+            let power = reference_power * measured_power;
+            result.coherence.push(if power > 0.0 {
+                (cross.norm_sqr() / power).min(1.0)
+            } else {
+                0.0
+            });
+        }
+
+        result
+    }
+}
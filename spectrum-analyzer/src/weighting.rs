@@ -0,0 +1,43 @@
+/// The frequency weighting curve to apply to the magnitude spectrum. The curves follow the
+/// definitions from IEC 61672-1 and are normalized to 0 dB at 1 kHz.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WeightingCurve {
+    /// No weighting, the spectrum is reported as is.
+    #[default]
+    None,
+    /// A-weighting, which approximates the sensitivity of the ear at low listening levels.
+    A,
+    /// C-weighting, which approximates the sensitivity of the ear at high listening levels.
+    C,
+}
+
+impl WeightingCurve {
+    /// Get the gain in decibels of the weighting curve at the given frequency in Hz.
+    pub fn gain_db(&self, frequency: f32) -> f32 {
+        // The pole frequencies from IEC 61672-1, squared as they only appear squared below.
+        const F1: f32 = 20.598_997 * 20.598_997;
+        const F2: f32 = 107.652_65 * 107.652_65;
+        const F3: f32 = 737.862_2 * 737.862_2;
+        const F4: f32 = 12_194.217 * 12_194.217;
+
+        let f2 = frequency * frequency;
+        match self {
+            WeightingCurve::None => 0.0,
+            WeightingCurve::A => {
+                let response = F4 * f2 * f2
+                    / ((f2 + F1) * ((f2 + F2) * (f2 + F3)).sqrt() * (f2 + F4));
+                20.0 * response.log10() + 2.0
+            }
+            WeightingCurve::C => {
+                let response = F4 * f2 / ((f2 + F1) * (f2 + F4));
+                20.0 * response.log10() + 0.062
+            }
+        }
+    }
+
+    /// Get the linear gain of the weighting curve at the given frequency in Hz. This is what gets
+    /// multiplied with the magnitudes.
+    pub fn gain(&self, frequency: f32) -> f32 {
+        10.0_f32.powf(self.gain_db(frequency) / 20.0)
+    }
+}
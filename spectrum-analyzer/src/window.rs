@@ -0,0 +1,123 @@
+use std::f64::consts::PI;
+use nih_plug::prelude::Enum;
+
+/// The window function that is applied to a frame before it is transformed. A window reduces the
+/// spectral leakage caused by analyzing a frame that does not contain a whole number of periods.
+///
+/// The `id` attributes are the ids the window parameter is saved with, so they must never change.
+/// A state that stores the window as a number is read with the numeric ids of
+/// [`WindowFunction::to_id()`] instead, which must never change either.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum WindowFunction {
+    /// No window at all. This gives the narrowest main lobe but the most leakage.
+    #[default]
+    #[id = "rectangular"]
+    Rectangular,
+    /// The Hann window, a good general purpose choice.
+    #[id = "hann"]
+    Hann,
+    /// The Hamming window, which has a lower first side lobe than the Hann window.
+    #[id = "hamming"]
+    Hamming,
+    /// The Blackman window, which trades a wider main lobe for lower side lobes.
+    #[id = "blackman"]
+    Blackman,
+    /// The 4-term Blackman-Harris window, which has very low side lobes.
+    #[id = "blackman-harris"]
+    #[name = "Blackman-Harris"]
+    BlackmanHarris,
+    /// The flat top window, which gives the most accurate amplitudes for sinusoids.
+    #[id = "flat-top"]
+    #[name = "Flat Top"]
+    FlatTop,
+}
+
+impl WindowFunction {
+    /// Get the stable numeric id of this window. When a state stores the window parameter as a
+    /// number rather than by its `id` attribute, `filter_state()` reads the number as one of these
+    /// ids, so they must never change, even if the variants are reordered. New windows must get a
+    /// new id.
+    ///
+    /// | Window           | Id |
+    /// |------------------|----|
+    /// | `Rectangular`    | 0  |
+    /// | `Hann`           | 1  |
+    /// | `Hamming`        | 2  |
+    /// | `Blackman`       | 3  |
+    /// | `BlackmanHarris` | 4  |
+    /// | `FlatTop`        | 5  |
+    pub fn to_id(&self) -> u32 {
+        match self {
+            WindowFunction::Rectangular => 0,
+            WindowFunction::Hann => 1,
+            WindowFunction::Hamming => 2,
+            WindowFunction::Blackman => 3,
+            WindowFunction::BlackmanHarris => 4,
+            WindowFunction::FlatTop => 5,
+        }
+    }
+
+    /// Get the window with the given stable numeric id, or `None` if there is no window with that
+    /// id. See [`WindowFunction::to_id()`].
+    pub fn from_id(id: u32) -> Option<WindowFunction> {
+        match id {
+            0 => Some(WindowFunction::Rectangular),
+            1 => Some(WindowFunction::Hann),
+            2 => Some(WindowFunction::Hamming),
+            3 => Some(WindowFunction::Blackman),
+            4 => Some(WindowFunction::BlackmanHarris),
+            5 => Some(WindowFunction::FlatTop),
+            _ => None,
+        }
+    }
+
+    /// Fill `coefficients` with the coefficients of this window for a frame of `len` samples.
+    pub fn fill(&self, coefficients: &mut Vec<f32>, len: usize) {
+        coefficients.clear();
+        coefficients.extend((0..len).map(|i| self.coefficient(i, len)));
+    }
+
+    /// Get the coherent gain of this window for a frame of `len` samples, which is the mean of its
+    /// coefficients. This is the factor the window scales the amplitude of a sinusoid by.
+    pub fn coherent_gain(&self, len: usize) -> f32 {
+        if len == 0 {
+            return 1.0;
+        }
+
+        (0..len).map(|i| self.coefficient(i, len)).sum::<f32>() / len as f32
+    }
+
+    /// Get the coefficient of this window for sample `i` of a frame of `len` samples.
+    pub fn coefficient(&self, i: usize, len: usize) -> f32 {
+        self.coefficient_f64(i, len) as f32
+    }
+
+    /// Get the coefficient of this window for sample `i` of a frame of `len` samples in double
+    /// precision.
+    pub fn coefficient_f64(&self, i: usize, len: usize) -> f64 {
+        if len <= 1 {
+            return 1.0;
+        }
+
+        // These are the periodic versions of the windows, which are the right choice for
+        // spectral analysis.
+        let phase = 2.0 * PI * i as f64 / len as f64;
+        match self {
+            WindowFunction::Rectangular => 1.0,
+            WindowFunction::Hann => 0.5 - 0.5 * phase.cos(),
+            WindowFunction::Hamming => 0.54 - 0.46 * phase.cos(),
+            WindowFunction::Blackman => {
+                0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos()
+            }
+            WindowFunction::BlackmanHarris => {
+                0.35875 - 0.48829 * phase.cos() + 0.14128 * (2.0 * phase).cos()
+                    - 0.01168 * (3.0 * phase).cos()
+            }
+            WindowFunction::FlatTop => {
+                0.215_578_95 - 0.416_631_58 * phase.cos() + 0.277_263_16 * (2.0 * phase).cos()
+                    - 0.083_578_95 * (3.0 * phase).cos()
+                    + 0.006_947_368 * (4.0 * phase).cos()
+            }
+        }
+    }
+}
@@ -1,7 +1,37 @@
 #[cfg(test)]
 mod tests {
+    use assert_no_alloc::assert_no_alloc;
     use nih_plug::buffer::Buffer;
-    use spectrum_analyzer::analyzer::Analyzer;
+    use nih_plug::prelude::{
+        AuxiliaryBuffers, BufferConfig, InitContext, Params, Plugin, PluginApi, PluginNoteEvent,
+        PluginState, ProcessContext, ProcessMode, ProcessStatus, Transport,
+    };
+    use nih_plug::wrapper::state::ParamValue;
+    use rustfft::FftPlanner;
+    use rustfft::num_complex::Complex;
+    use std::cell::{Cell, RefCell};
+    use std::collections::BTreeMap;
+    use spectrum_analyzer::analyzer::{
+        Analyzer, AnalyzerResult, ChannelMode, MagnitudeScale, DEFAULT_FLOOR_DB,
+        DEFAULT_REFERENCE_A4,
+    };
+    use spectrum_analyzer::analyzer_f64::AnalyzerF64;
+    use spectrum_analyzer::builder::AnalyzerBuilder;
+    use spectrum_analyzer::config::{AnalyzerConfig, ConfigError};
+    use spectrum_analyzer::editor::{frequency_to_x, x_to_frequency};
+    use spectrum_analyzer::handoff::{results_channel, REFERENCE_LABEL};
+    use spectrum_analyzer::mel::{hz_to_mel, mel_to_hz};
+    use spectrum_analyzer::notes::{frequency_to_cc, note_to_hz, HeldNotes};
+    use spectrum_analyzer::plugin::{
+        process_status, AnalyzerTask, FftSize, SpectrumAnalyzer, SpectrumAnalyzerParams,
+    };
+    use spectrum_analyzer::settings::AnalyzerSettings;
+    use spectrum_analyzer::state::{migrate, ReferenceCurve};
+    use spectrum_analyzer::sweep::ExponentialSweep;
+    use spectrum_analyzer::testsignals::{impulse, pink_noise, sine, white_noise};
+    use spectrum_analyzer::transfer::TransferResult;
+    use spectrum_analyzer::weighting::WeightingCurve;
+    use spectrum_analyzer::window::WindowFunction;
 
     #[test]
     fn analyzer_creates_with_default_sample_rate() {
@@ -24,6 +54,45 @@ mod tests {
         assert!(results.is_empty());
     }
 
+    #[test]
+    fn process_returns_empty_for_channels_without_samples() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let mut channel1_data: Vec<f32> = Vec::new();
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(0, |output_slices| *output_slices = vec![&mut channel1_data]);
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn process_pads_a_single_sample_to_a_full_frame() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let fft_size = analyzer.fft_size();
+        let mut channel1_data = vec![1.0];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1, |output_slices| *output_slices = vec![&mut channel1_data]);
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].magnitudes.len(), fft_size / 2);
+        assert_eq!(results[0].frequencies.len(), fft_size / 2);
+        assert!(results[0].magnitudes.iter().all(|magnitude| magnitude.is_finite()));
+        assert_eq!(results[0].peak, 1.0);
+    }
+
     #[test]
     fn process_returns_results_for_single_channel() {
         // Arrange
@@ -46,6 +115,32 @@ mod tests {
         assert!(!results[0].frequencies.is_empty());
     }
 
+    #[test]
+    fn process_leaves_the_buffer_unchanged() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_smoothing(0.5);
+        let signal = white_noise(0x2468_ace0, 1024);
+        let reversed = signal.iter().rev().copied().collect::<Vec<_>>();
+        let (mut channel1_data, mut channel2_data) = (signal.clone(), reversed.clone());
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data, &mut channel2_data]
+            });
+        }
+
+        // Act
+        analyzer.process(&mut buffer);
+        analyzer.process(&mut buffer);
+
+        // Assert
+        let channels = buffer.as_slice_immutable();
+        let bits = |samples: &[f32]| samples.iter().map(|s| s.to_bits()).collect::<Vec<_>>();
+        assert_eq!(bits(channels[0]), bits(&signal));
+        assert_eq!(bits(channels[1]), bits(&reversed));
+    }
+
     #[test]
     fn process_returns_correct_number_of_results_for_multiple_channels() {
         // Arrange
@@ -85,13 +180,23 @@ mod tests {
         let result = &results[0];
         assert_eq!(result.magnitudes.len(), 512); // FFT size / 2
         assert_eq!(result.frequencies.len(), 512); // FFT size / 2
+
+        // Act
+        analyzer.set_include_nyquist(true);
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        let result = &results[0];
+        assert_eq!(result.magnitudes.len(), 513); // FFT size / 2 + 1
+        assert_eq!(result.frequencies.len(), 513); // FFT size / 2 + 1
+        assert_eq!(result.frequencies[512], 22050.0);
     }
 
     #[test]
     fn frequencies_are_calculated_correctly() {
         // Arrange
         let mut analyzer = Analyzer::new(44100.0);
-        let mut channel1_data = vec![1.0; 1024];
+        let mut channel1_data = sine(1000.0, 44100.0, 1024);
         let mut buffer = Buffer::default();
         unsafe {
             buffer.set_slices(1024, |output_slices| {
@@ -106,5 +211,2651 @@ mod tests {
         let result = &results[0];
         let expected_frequency_step = 44100.0 / 1024.0;
         assert_eq!(result.frequencies[1] - result.frequencies[0], expected_frequency_step);
+        let (peak_frequency, _) = Analyzer::dominant_peak(result).unwrap();
+        assert!((peak_frequency - 1000.0).abs() < expected_frequency_step);
+    }
+
+    #[test]
+    fn a_weighting_is_normalized_at_1khz() {
+        let gain = WeightingCurve::A.gain_db(1000.0);
+        assert!(gain.abs() < 0.1, "A-weighting at 1 kHz was {gain} dB");
+    }
+
+    #[test]
+    fn a_weighting_attenuates_100hz() {
+        let gain = WeightingCurve::A.gain_db(100.0);
+        assert!((gain + 19.1).abs() < 0.2, "A-weighting at 100 Hz was {gain} dB");
+    }
+
+    #[test]
+    fn weighting_is_applied_to_the_magnitudes() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_weighting(WeightingCurve::A);
+        let mut channel1_data = vec![1.0; 1024];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        // A constant signal only has a DC component, and A-weighting removes DC completely.
+        assert_eq!(results[0].magnitudes[0], 0.0);
+    }
+
+    #[test]
+    fn slope_deviation_of_flat_spectrum_is_negated_target() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_fft_size(4096);
+        let mut channel1_data = vec![0.0; 4096];
+        channel1_data[0] = 1.0;
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(4096, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        analyzer.process(&mut buffer);
+
+        // Assert
+        // An impulse has a perfectly flat spectrum, so it is 4.5 dB/oct brighter than the target.
+        let deviation = analyzer.slope_deviation(-4.5);
+        assert!((deviation - 4.5).abs() < 0.01, "deviation was {deviation}");
+    }
+
+    #[test]
+    fn channel_override_replaces_global_config() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_channel_override(1, AnalyzerConfig {
+            fft_size: 2048,
+            window: WindowFunction::Hann,
+            ..AnalyzerConfig::default()
+        });
+        let mut channel1_data = vec![0.0; 2048];
+        let mut channel2_data = vec![0.0; 2048];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(2048, |output_slices| {
+                *output_slices = vec![&mut channel1_data, &mut channel2_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        assert_eq!(results[0].magnitudes.len(), 512);
+        assert_eq!(results[1].magnitudes.len(), 1024);
+        assert_eq!(analyzer.channel_config(1).window, WindowFunction::Hann);
+
+        analyzer.clear_channel_override(1);
+        assert_eq!(analyzer.channel_config(1), analyzer.config());
+    }
+
+    #[test]
+    fn window_ids_are_stable() {
+        let windows = [
+            (WindowFunction::Rectangular, 0),
+            (WindowFunction::Hann, 1),
+            (WindowFunction::Hamming, 2),
+            (WindowFunction::Blackman, 3),
+            (WindowFunction::BlackmanHarris, 4),
+            (WindowFunction::FlatTop, 5),
+        ];
+
+        for (window, id) in windows {
+            assert_eq!(window.to_id(), id);
+            assert_eq!(WindowFunction::from_id(id), Some(window));
+        }
+        assert_eq!(WindowFunction::from_id(6), None);
+    }
+
+    #[test]
+    fn results_carry_the_index_of_their_channel() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let mut channel1_data = vec![0.0; 1024];
+        let mut channel2_data = vec![0.0; 1024];
+        let mut channel3_data = vec![0.0; 1024];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data, &mut channel2_data, &mut channel3_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        let channels = results.iter().map(|result| result.channel).collect::<Vec<_>>();
+        assert_eq!(channels, [0, 1, 2]);
+    }
+
+    #[test]
+    fn mid_side_mode_analyzes_mid_and_side_signals() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_channel_mode(ChannelMode::MidSide);
+        let mut channel1_data = vec![1.0; 1024];
+        let mut channel2_data = vec![1.0; 1024];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data, &mut channel2_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        // Identical channels have all of their energy in the mid signal.
+        assert_eq!(results[0].label.as_deref(), Some("Mid"));
+        assert_eq!(results[1].label.as_deref(), Some("Side"));
+        assert_eq!(results[0].magnitudes[0], 1.0);
+        assert!(results[1].magnitudes.iter().all(|&magnitude| magnitude == 0.0));
+    }
+
+    #[test]
+    fn mid_side_mode_falls_back_to_discrete_for_mono() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_channel_mode(ChannelMode::MidSide);
+        let mut channel1_data = vec![0.0; 1024];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].label, None);
+    }
+
+    #[test]
+    fn sum_mode_of_identical_channels_matches_a_single_channel() {
+        // Arrange
+        let mut summing = Analyzer::new(44100.0);
+        summing.set_channel_mode(ChannelMode::Sum);
+        let mut single = Analyzer::new(44100.0);
+        let tone = sine(1000.0, 44100.0, 1024);
+        let (mut channel1_data, mut channel2_data) = (tone.clone(), tone.clone());
+        let mut single_data = tone;
+        let mut stereo = Buffer::default();
+        let mut mono = Buffer::default();
+        unsafe {
+            stereo.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data, &mut channel2_data]
+            });
+            mono.set_slices(1024, |output_slices| *output_slices = vec![&mut single_data]);
+        }
+
+        // Act
+        let summed = summing.process(&mut stereo).to_vec();
+        let expected = single.process(&mut mono);
+
+        // Assert
+        assert_eq!(summed.len(), 1);
+        assert_eq!(summed[0].label.as_deref(), Some("Sum"));
+        assert_eq!(summed[0].magnitudes, expected[0].magnitudes);
+    }
+
+    #[test]
+    fn spectral_centroid_of_pure_tone_is_its_frequency() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let bin_width = 44100.0 / 1024.0;
+        let frequency = 100.0 * bin_width;
+        let mut channel1_data = sine(frequency, 44100.0, 1024);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+        let centroid = Analyzer::spectral_centroid(&results[0]);
+
+        // Assert
+        assert!((centroid - frequency).abs() < bin_width, "centroid was {centroid} Hz");
+    }
+
+    #[test]
+    fn process_db_returns_magnitudes_in_db() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_amplitude_correction(false);
+        let mut channel1_data = vec![0.0; 1024];
+        channel1_data[0] = 1.0;
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let magnitudes = analyzer.process_db(&mut buffer);
+
+        // Assert
+        // Without amplitude correction an impulse has a magnitude of 1, or 0 dB, in every bin.
+        assert_eq!(magnitudes.len(), 1);
+        assert_eq!(magnitudes[0].len(), analyzer.frequencies().len());
+        assert!(magnitudes[0].iter().all(|&magnitude| magnitude.abs() < 1e-4));
+    }
+
+    #[test]
+    fn frequencies_are_cached_until_sample_rate_changes() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let mut channel1_data = vec![0.0; 1024];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer).to_vec();
+
+        // Assert
+        assert_eq!(analyzer.frequencies(), &results[0].frequencies[..]);
+
+        analyzer.set_sample_rate(48000.0);
+        assert_eq!(analyzer.frequencies()[1], 48000.0 / 1024.0);
+    }
+
+    #[test]
+    fn parabolic_interpolation_finds_the_vertex() {
+        // Arrange
+        // Both triplets are samples of parabolas, `1 - x²` and `1 - (x - 0.25)²`.
+        let symmetric = (0.0, 1.0, 0.0);
+        let asymmetric = (1.0 - 1.25_f32.powi(2), 1.0 - 0.25_f32.powi(2), 1.0 - 0.75_f32.powi(2));
+
+        // Act
+        let (symmetric_offset, symmetric_peak) =
+            Analyzer::parabolic_interpolate(symmetric.0, symmetric.1, symmetric.2);
+        let (asymmetric_offset, asymmetric_peak) =
+            Analyzer::parabolic_interpolate(asymmetric.0, asymmetric.1, asymmetric.2);
+
+        // Assert
+        assert_eq!((symmetric_offset, symmetric_peak), (0.0, 1.0));
+        assert!((asymmetric_offset - 0.25).abs() < 1e-6);
+        assert!((asymmetric_peak - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn spectral_flatness_of_noise_is_higher_than_of_a_tone() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let mut noise_data = white_noise(0x1234_5678, 1024);
+        let mut tone_data = sine(100.0, 1024.0, 1024);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut noise_data, &mut tone_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+        let noise_flatness = Analyzer::spectral_flatness(&results[0]);
+        let tone_flatness = Analyzer::spectral_flatness(&results[1]);
+
+        // Assert
+        assert!(noise_flatness > 0.5, "noise flatness was {noise_flatness}");
+        assert!(tone_flatness < 0.1, "tone flatness was {tone_flatness}");
+    }
+
+    #[test]
+    fn spectral_rolloff_of_a_dark_signal_is_lower_than_of_a_bright_one() {
+        use spectrum_analyzer::analyzer::DEFAULT_ROLLOFF_PERCENTAGE;
+
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let tone = |frequency: f32, amplitude: f32| {
+            sine(frequency, 44100.0, 1024).into_iter().map(move |sample| amplitude * sample)
+        };
+        let mut dark_data =
+            tone(200.0, 1.0).zip(tone(8000.0, 0.05)).map(|(a, b)| a + b).collect::<Vec<_>>();
+        let mut bright_data =
+            tone(200.0, 0.05).zip(tone(8000.0, 1.0)).map(|(a, b)| a + b).collect::<Vec<_>>();
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut dark_data, &mut bright_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+        let dark_rolloff = Analyzer::spectral_rolloff(&results[0], DEFAULT_ROLLOFF_PERCENTAGE);
+        let bright_rolloff = Analyzer::spectral_rolloff(&results[1], DEFAULT_ROLLOFF_PERCENTAGE);
+
+        // Assert
+        assert!(dark_rolloff < 1000.0, "dark rolloff was {dark_rolloff}");
+        assert!(bright_rolloff > 7000.0, "bright rolloff was {bright_rolloff}");
+    }
+
+    #[test]
+    fn phases_are_only_computed_when_enabled() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let mut channel1_data = (0..1024)
+            .map(|i| (2.0 * std::f32::consts::PI * 8.0 * i as f32 / 1024.0).cos())
+            .collect::<Vec<_>>();
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let without_phases = analyzer.process(&mut buffer).to_vec();
+        analyzer.set_phase_output(true);
+        let with_phases = analyzer.process(&mut buffer);
+
+        // Assert
+        // A cosine starting at the beginning of the frame has a phase of zero.
+        assert!(without_phases[0].phases.is_none());
+        let phases = with_phases[0].phases.as_ref().unwrap();
+        assert_eq!(phases.len(), 512);
+        assert!(phases[8].abs() < 1e-3, "phase was {}", phases[8]);
+    }
+
+    #[test]
+    fn captured_impulse_response_recovers_the_delay() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.start_sweep_capture(ExponentialSweep {
+            start_frequency: 50.0,
+            end_frequency: 15_000.0,
+            duration: 0.1,
+        });
+        let mut excitation = vec![0.0; 8192];
+        analyzer.play_sweep(&mut excitation);
+
+        // Act
+        // The measured system delays the signal by 10 samples.
+        let mut response = vec![0.0; 10];
+        response.extend_from_slice(&excitation[..8182]);
+        analyzer.record_sweep_response(&response);
+        let impulse_response = analyzer.capture_impulse_response();
+
+        // Assert
+        let (peak_index, peak) = impulse_response
+            .samples
+            .iter()
+            .enumerate()
+            .fold((0, 0.0_f32), |(i, max), (j, &sample)| {
+                if sample.abs() > max { (j, sample.abs()) } else { (i, max) }
+            });
+        assert_eq!(peak_index, 10);
+        assert!((peak - 1.0).abs() < 0.01, "peak was {peak}");
+        assert!(!impulse_response.magnitudes.is_empty());
+    }
+
+    #[test]
+    fn phase_smoothing_unwraps_the_phase() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_phase_output(true);
+        analyzer.set_phase_smoothing(1.0 / 3.0);
+        let mut channel1_data = vec![0.0; 1024];
+        channel1_data[3] = 1.0;
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        // A delayed impulse has a linear phase, which keeps falling once it's unwrapped.
+        let phases = results[0].phases.as_ref().unwrap();
+        assert!(phases.windows(2).all(|pair| pair[1] <= pair[0]));
+        assert!(phases[511] < -std::f32::consts::PI);
+    }
+
+    #[test]
+    fn dominant_peak_finds_440hz() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_fft_size(4096);
+        analyzer.set_window(WindowFunction::Hann);
+        let mut channel1_data = sine(440.0, 44100.0, 4096);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(4096, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+        let (frequency, _) = Analyzer::dominant_peak(&results[0]).unwrap();
+
+        // Assert
+        assert!((frequency - 440.0).abs() < 2.0, "peak was at {frequency} Hz");
+    }
+
+    #[test]
+    fn dominant_peak_of_silence_is_none() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let mut channel1_data = vec![0.0; 1024];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        assert_eq!(Analyzer::dominant_peak(&results[0]), None);
+    }
+
+    #[test]
+    fn smart_resolution_is_continuous_at_the_crossover() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_fft_size(4096);
+        analyzer.set_window(WindowFunction::Hann);
+        // This tone falls on a bin of both the large and the small FFT.
+        let bin = 96;
+        let frequency = bin as f32 * 44100.0 / 4096.0;
+        let mut channel1_data = sine(frequency, 44100.0, 4096);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(4096, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let large = analyzer.process(&mut buffer).to_vec();
+        analyzer.set_smart_resolution(frequency);
+        let blended = analyzer.process(&mut buffer);
+
+        // Assert
+        let expected = large[0].magnitudes[bin];
+        let actual = blended[0].magnitudes[bin];
+        assert!((actual - expected).abs() / expected < 0.01, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn points_pair_frequencies_with_magnitudes() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let mut channel1_data = vec![1.0; 1024];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+        let points = results[0].points();
+        let points_db = results[0].points_db(DEFAULT_FLOOR_DB);
+
+        // Assert
+        assert_eq!(points.len(), results[0].magnitudes.len());
+        assert_eq!(points[0], [0.0, 1.0]);
+        assert_eq!(points[1][0], results[0].frequencies[1]);
+        assert!(points_db[0][1].abs() < 1e-4);
+        assert_eq!(points_db[100][1], DEFAULT_FLOOR_DB);
+        assert!(points_db.iter().all(|point| point[1] >= DEFAULT_FLOOR_DB));
+    }
+
+    #[test]
+    fn process_reuses_the_result_buffers() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let mut channel1_data = vec![1.0; 1024];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let first = analyzer.process(&mut buffer)[0].magnitudes.as_ptr();
+        let second = analyzer.process(&mut buffer)[0].magnitudes.as_ptr();
+
+        // Assert
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn channels_of_different_lengths_are_not_analyzed() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let mut channel1_data = vec![1.0; 1024];
+        let mut channel2_data = vec![1.0; 512];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data, &mut channel2_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn streaming_analysis_calls_back_once_per_frame() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let channel1_data = vec![1.0; 4096];
+        let channel2_data = vec![0.5; 4096];
+
+        // Act
+        let mut frames = Vec::new();
+        analyzer.process_samples_streaming(&[&channel1_data, &channel2_data], 512, |results| {
+            frames.push((results.len(), results[1].magnitudes[0]));
+        });
+
+        // Assert
+        // The first frame ends at 1024 samples, and then there are six more hops of 512.
+        assert_eq!(frames.len(), 7);
+        assert!(frames.iter().all(|&(channels, dc)| channels == 2 && dc == 0.5));
+    }
+
+    #[test]
+    fn double_precision_analysis_is_exact_for_long_frames() {
+        // Arrange
+        let mut analyzer = AnalyzerF64::new(44100.0);
+        let config = AnalyzerConfig { fft_size: 65536, ..AnalyzerConfig::default() };
+        analyzer.apply_config(config).unwrap();
+        let channel1_data = vec![0.1; 65536];
+
+        // Act
+        let results = analyzer.process(&[&channel1_data]);
+
+        // Assert
+        // The DC bin is the sum of all samples, computed in double precision.
+        let expected = (0.1_f32 as f64 * 65536.0) as f32;
+        assert_eq!(results[0].magnitudes.len(), 32768);
+        assert_eq!(results[0].magnitudes[0], expected);
+    }
+
+    #[test]
+    fn invalid_configs_are_rejected() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let too_small = AnalyzerConfig { fft_size: 8, ..AnalyzerConfig::default() };
+        let too_large = AnalyzerConfig { fft_size: 1 << 20, ..AnalyzerConfig::default() };
+
+        // Act
+        let result = analyzer.apply_config(too_small);
+
+        // Assert
+        assert_eq!(result, Err(ConfigError::FftSizeTooSmall(8)));
+        assert_eq!(analyzer.fft_size(), 1024);
+        assert_eq!(too_large.validate(44100.0), Err(ConfigError::FftSizeTooLarge(1 << 20)));
+        assert_eq!(
+            AnalyzerConfig::default().validate(0.0),
+            Err(ConfigError::InvalidSampleRate(0.0))
+        );
+        assert_eq!(AnalyzerConfig::default().validate(44100.0), Ok(()));
+    }
+
+    #[test]
+    fn psd_of_white_noise_is_flat() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_window(WindowFunction::Hann);
+        let mut noise_data = white_noise(0x1234_5678, 65536);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(65536, |output_slices| {
+                *output_slices = vec![&mut noise_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process_psd(&mut buffer, 1024, 0.5);
+
+        // Assert
+        // Uniform noise in [-1, 1) has a variance of 1/3.
+        let expected = 2.0 / 3.0 / 44100.0;
+        let power = &results[0].power[1..];
+        let mean = power.iter().sum::<f32>() / power.len() as f32;
+        assert_eq!(results[0].frequencies.len(), 512);
+        assert!((mean - expected).abs() / expected < 0.05, "{mean} != {expected}");
+        assert!(power.iter().all(|&bin| (bin - expected).abs() / expected < 0.5));
+    }
+
+    #[test]
+    fn eight_channels_match_analyzing_each_channel_alone() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_window(WindowFunction::Hann);
+        let mut channel_data = (0..8)
+            .map(|channel| {
+                (0..1024)
+                    .map(|i| (i as f32 * 0.01 * (channel + 1) as f32).sin())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let expected = channel_data
+            .iter_mut()
+            .enumerate()
+            .map(|(channel, data)| {
+                let mut single = Analyzer::new(44100.0);
+                single.set_window(WindowFunction::Hann);
+                let mut buffer = Buffer::default();
+                unsafe {
+                    buffer.set_slices(1024, |output_slices| *output_slices = vec![data]);
+                }
+                AnalyzerResult { channel, ..single.process(&mut buffer)[0].clone() }
+            })
+            .collect::<Vec<_>>();
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = channel_data.iter_mut().map(|data| &mut data[..]).collect()
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        assert_eq!(results, &expected[..]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn synthetic_result_replaces_the_analysis() {
+        use spectrum_analyzer::analyzer::AnalyzerResult;
+
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let synthetic = AnalyzerResult {
+            frequencies: vec![0.0, 100.0, 200.0],
+            magnitudes: vec![1.0, 0.5, 0.25],
+            label: Some(String::from("Synthetic")),
+            channel: 0,
+            phases: None,
+            start_bin: 0,
+            rms: 0.0,
+            peak: 0.0,
+        };
+        let mut channel1_data = vec![1.0; 1024];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        analyzer.set_synthetic(synthetic.clone());
+        let results = analyzer.process(&mut buffer).to_vec();
+        analyzer.clear_synthetic();
+        let analyzed = analyzer.process(&mut buffer);
+
+        // Assert
+        assert_eq!(results, vec![synthetic]);
+        assert_eq!(analyzed[0].magnitudes.len(), 512);
+    }
+
+    #[test]
+    fn pushed_samples_are_analyzed_once_a_frame_is_complete() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let mut channel1_data = sine(1000.0, 44100.0, 1024);
+
+        // Act
+        analyzer.push_samples(0, &channel1_data[..512]);
+        let incomplete = analyzer.poll();
+        analyzer.push_samples(0, &channel1_data[512..]);
+        let complete = analyzer.poll();
+        let drained = analyzer.poll();
+
+        // Assert
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+        let expected = analyzer.process(&mut buffer).to_vec();
+        assert!(incomplete.is_none());
+        assert_eq!(complete, Some(expected));
+        assert!(drained.is_none());
+    }
+
+    #[test]
+    fn relative_group_delay_of_a_delayed_impulse_is_the_delay() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_phase_output(true);
+        let mut delayed_data = vec![0.0; 1024];
+        delayed_data[10] = 1.0;
+        let mut reference_data = vec![0.0; 1024];
+        reference_data[0] = 1.0;
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut delayed_data, &mut reference_data]
+            });
+        }
+
+        // Act
+        analyzer.process(&mut buffer);
+        let without_reference = analyzer.relative_group_delay();
+        analyzer.set_reference_channel(Some(1));
+        let delays = analyzer.relative_group_delay().unwrap();
+
+        // Assert
+        let expected = 10.0 / 44100.0;
+        assert!(without_reference.is_none());
+        assert!(delays.iter().all(|delay| (delay - expected).abs() < 1e-6));
+    }
+
+    #[test]
+    fn thd_of_a_tone_with_a_second_harmonic_matches_its_level() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_fft_size(4096);
+        analyzer.set_window(WindowFunction::Hann);
+        let mut channel1_data = sine(1000.0, 44100.0, 4096)
+            .into_iter()
+            .zip(sine(2000.0, 44100.0, 4096))
+            .map(|(fundamental, harmonic)| fundamental + 0.1 * harmonic)
+            .collect::<Vec<_>>();
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(4096, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+        let thd = Analyzer::thd(&results[0], 1000.0, 5);
+
+        // Assert
+        assert!((thd - 10.0).abs() < 0.5, "THD was {thd}%");
+    }
+
+    #[test]
+    fn constant_q_transform_peaks_at_the_note_of_a_tone() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let mut channel1_data = sine(440.0, 44100.0, 8192);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(8192, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process_cqt(&mut buffer, 12, 110.0);
+
+        // Assert
+        // A4 is two octaves above A2.
+        let result = &results[0];
+        let peak_bin = result
+            .magnitudes
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(bin, _)| bin)
+            .unwrap();
+        assert_eq!(peak_bin, 24);
+        assert!((result.center_freqs[24] - 440.0).abs() < 0.01);
+        assert!((result.magnitudes[24] - 0.5).abs() < 0.02);
+    }
+
+    #[test]
+    fn chromagram_of_a_c_major_triad_peaks_at_c_e_and_g() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_fft_size(8192);
+        analyzer.set_window(WindowFunction::Hann);
+        let mut channel1_data = vec![0.0; 8192];
+        for frequency in [261.63, 329.63, 392.0] {
+            for (sample, tone) in channel1_data.iter_mut().zip(sine(frequency, 44100.0, 8192)) {
+                *sample += tone;
+            }
+        }
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(8192, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+        let chroma = Analyzer::chromagram(&results[0], DEFAULT_REFERENCE_A4);
+
+        // Assert
+        let mut pitch_classes = (0..12).collect::<Vec<_>>();
+        pitch_classes.sort_by(|&a, &b| chroma[b].total_cmp(&chroma[a]));
+        pitch_classes.truncate(3);
+        pitch_classes.sort();
+        assert_eq!(pitch_classes, vec![0, 4, 7]);
+    }
+
+    #[test]
+    fn mel_band_around_a_tone_has_the_most_energy() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_window(WindowFunction::Hann);
+        let mut channel1_data = sine(1000.0, 44100.0, 1024);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process_mel(&mut buffer, 40, 0.0, 8000.0);
+
+        // Assert
+        let result = &results[0];
+        let loudest = result
+            .energies
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(band, _)| band)
+            .unwrap();
+        let nearest = result
+            .center_freqs
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| (*a - 1000.0).abs().total_cmp(&(*b - 1000.0).abs()))
+            .map(|(band, _)| band)
+            .unwrap();
+        assert_eq!(result.energies.len(), 40);
+        assert_eq!(loudest, nearest);
+        assert!((hz_to_mel(700.0) - 2595.0 * 2.0_f32.log10()).abs() < 1e-3);
+        assert!((mel_to_hz(hz_to_mel(1234.0)) - 1234.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn multiresolution_resolves_low_frequencies_more_finely() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_window(WindowFunction::Hann);
+        let mut channel1_data = sine(200.0, 44100.0, 4096)
+            .into_iter()
+            .zip(sine(8000.0, 44100.0, 4096))
+            .map(|(low, high)| low + high)
+            .collect::<Vec<_>>();
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(4096, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let regular = analyzer.process(&mut buffer).to_vec();
+        analyzer.set_multiresolution(true);
+        let multiresolution = analyzer.process(&mut buffer);
+
+        // Assert
+        let result = &multiresolution[0];
+        assert_eq!(result.frequencies.len(), 256 + 512 - 64);
+        assert_eq!(result.magnitudes.len(), result.frequencies.len());
+        assert!(result.frequencies.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(result.frequencies[1], 44100.0 / 4096.0);
+
+        let low_peak =
+            (0..256).max_by(|&a, &b| result.magnitudes[a].total_cmp(&result.magnitudes[b]));
+        let low_peak_frequency = result.frequencies[low_peak.unwrap()];
+        assert!((low_peak_frequency - 200.0).abs() < 44100.0 / 4096.0);
+
+        // The high frequencies are the same as in the regular analysis.
+        assert_eq!(result.magnitudes[256..], regular[0].magnitudes[64..]);
+    }
+
+    #[test]
+    fn noise_floor_tracks_the_noise_and_not_the_tone() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_window(WindowFunction::Hann);
+        let mut noise_data =
+            white_noise(0x1234_5678, 1024).iter().map(|noise| 0.001 * noise).collect::<Vec<_>>();
+        let mut noisy_tone_data = noise_data
+            .iter()
+            .zip(sine(1000.0, 44100.0, 1024))
+            .map(|(noise, tone)| noise + tone)
+            .collect::<Vec<_>>();
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut noise_data, &mut noisy_tone_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+        let noise_floor = Analyzer::noise_floor(&results[0]);
+        let noisy_tone_floor = Analyzer::noise_floor(&results[1]);
+
+        // Assert
+        let difference = (noisy_tone_floor - noise_floor).abs();
+        assert!(difference < 3.0, "{noisy_tone_floor} != {noise_floor}");
+    }
+
+    #[test]
+    fn amplitude_correction_makes_magnitudes_independent_of_the_fft_size() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        // This tone falls on a bin for both FFT sizes.
+        let frequency = 64.0 * 44100.0 / 4096.0;
+        let mut channel1_data = sine(frequency, 44100.0, 4096);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(4096, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let small = analyzer.process(&mut buffer)[0].magnitudes[16];
+        analyzer.set_fft_size(4096);
+        let large = analyzer.process(&mut buffer)[0].magnitudes[64];
+        analyzer.set_amplitude_correction(false);
+        let uncorrected = analyzer.process(&mut buffer)[0].magnitudes[64];
+
+        // Assert
+        assert!((small - 1.0).abs() < 1e-3, "small was {small}");
+        assert!((large - 1.0).abs() < 1e-3, "large was {large}");
+        assert!((uncorrected - 2048.0).abs() < 1.0, "uncorrected was {uncorrected}");
+    }
+
+    #[test]
+    fn coherent_gain_compensation_makes_windowed_peaks_match_the_rectangular_window() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let frequency = 32.0 * 44100.0 / 1024.0;
+        let mut channel1_data = sine(frequency, 44100.0, 1024);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let rectangular = analyzer.process(&mut buffer)[0].magnitudes[32];
+        analyzer.set_window(WindowFunction::Hann);
+        let hann = analyzer.process(&mut buffer)[0].magnitudes[32];
+
+        // Assert
+        assert!((analyzer.window_coherent_gain() - 0.5).abs() < 1e-4);
+        assert!((hann - rectangular).abs() < 0.01, "{hann} != {rectangular}");
+    }
+
+    #[test]
+    fn changing_the_sample_rate_updates_the_frequencies_and_flushes_queued_samples() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let mut channel1_data = vec![1.0; 1024];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+        analyzer.process(&mut buffer);
+        analyzer.push_samples(0, &[0.0; 512]);
+
+        // Act
+        analyzer.set_sample_rate(48000.0);
+        analyzer.push_samples(0, &[0.0; 512]);
+        let polled = analyzer.poll();
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        assert!(polled.is_none());
+        let step = results[0].frequencies[1] - results[0].frequencies[0];
+        assert_eq!(step, 48000.0 / 1024.0);
+    }
+
+    #[test]
+    fn flush_analyzes_less_than_a_frame_of_queued_samples() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.push_samples(0, &[1.0; 300]);
+        let polled = analyzer.poll();
+
+        // Act
+        let flushed = analyzer.flush();
+        let flushed_again = analyzer.flush();
+
+        // Assert
+        assert!(polled.is_none());
+        assert_eq!(flushed.len(), 1);
+        assert!(flushed[0].magnitudes[0] > 0.0);
+        assert!(flushed_again.is_empty());
+    }
+
+    #[test]
+    fn overlapping_frames_are_polled_from_fewer_samples() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_overlap(0.5);
+        analyzer.push_samples(0, &[1.0; 2048]);
+
+        // Act
+        let frames = std::iter::from_fn(|| analyzer.poll()).count();
+
+        // Assert
+        // With half of every frame shared, 1024 sample frames start every 512 samples.
+        assert_eq!(frames, 3);
+    }
+
+    #[test]
+    fn the_offline_profile_overlaps_the_polled_frames_more() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_fft_size(256);
+        analyzer.set_offline(true);
+
+        // Act
+        analyzer.push_samples(0, &[1.0; 2048]);
+        let offline_frames = std::iter::from_fn(|| analyzer.poll()).count();
+        analyzer.set_offline(false);
+        analyzer.reset();
+        analyzer.push_samples(0, &[1.0; 2048]);
+        let realtime_frames = std::iter::from_fn(|| analyzer.poll()).count();
+
+        // Assert
+        // Offline, 1024 sample frames start every 256 samples, and without the profile 256 sample
+        // frames don't overlap at all.
+        assert_eq!(offline_frames, 5);
+        assert_eq!(realtime_frames, 8);
+        assert_eq!(analyzer.overlap(), 0.0);
+    }
+
+    #[test]
+    fn reset_discards_queued_samples() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.prepare(1, 1024);
+        analyzer.push_samples(0, &[1.0; 512]);
+
+        // Act
+        analyzer.reset();
+        analyzer.push_samples(0, &[1.0; 512]);
+        let polled = analyzer.poll();
+
+        // Assert
+        assert!(polled.is_none());
+    }
+
+    #[test]
+    fn results_channel_hands_the_latest_results_to_another_thread() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let (mut input, mut output) = results_channel(1, 0, analyzer.max_bin_count(1));
+        let unpublished = output.read().len();
+
+        // Act
+        let writer = std::thread::spawn(move || {
+            for amplitude in [0.25, 0.5, 1.0] {
+                let mut channel1_data = vec![amplitude; 1024];
+                let mut buffer = Buffer::default();
+                unsafe {
+                    buffer.set_slices(1024, |output_slices| {
+                        *output_slices = vec![&mut channel1_data]
+                    });
+                }
+                input.publish(analyzer.process(&mut buffer), &[]);
+            }
+        });
+        writer.join().unwrap();
+        let results = output.read();
+
+        // Assert
+        assert_eq!(unpublished, 0);
+        assert_eq!(results.len(), 1);
+        assert!((results[0].magnitudes[0] - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn smoothing_blends_every_frame_with_the_previous_one() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_smoothing(0.5);
+        let mut channel1_data = vec![1.0; 1024];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+        analyzer.process(&mut buffer);
+        let mut silence = vec![0.0; 1024];
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut silence]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        assert!((results[0].magnitudes[0] - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn a_slope_of_three_db_per_octave_flattens_pink_noise() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_fft_size(4096);
+        // Pink noise loses 3 dB per octave, so every bin gets an amplitude of `1 / sqrt(bin)`.
+        // The phases come from white noise with a fixed seed so the test is repeatable.
+        let phases = white_noise(1, 2048);
+        let mut channel1_data = vec![0.0; 4096];
+        for (bin, noise) in phases.iter().enumerate().skip(1) {
+            let phase = noise * std::f32::consts::PI;
+            let amplitude = 1.0 / (bin as f32).sqrt();
+            for (i, sample) in channel1_data.iter_mut().enumerate() {
+                let angle = 2.0 * std::f32::consts::PI * bin as f32 * i as f32 / 4096.0;
+                *sample += amplitude * (angle + phase).sin();
+            }
+        }
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(4096, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+        let spread = |magnitudes: &[f32]| {
+            let db = magnitudes[8..2048].iter().map(|m| 20.0 * m.log10()).collect::<Vec<_>>();
+            let max = db.iter().copied().fold(f32::MIN, f32::max);
+            let min = db.iter().copied().fold(f32::MAX, f32::min);
+            max - min
+        };
+
+        // Act
+        let untilted = spread(&analyzer.process(&mut buffer)[0].magnitudes);
+        analyzer.set_slope_db_per_octave(3.0);
+        let tilted = spread(&analyzer.process(&mut buffer)[0].magnitudes);
+
+        // Assert
+        assert!(untilted > 20.0);
+        assert!(tilted < 1.0);
+    }
+
+    #[test]
+    fn frozen_results_do_not_change_with_new_samples() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let mut channel1_data = vec![1.0; 1024];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+        let before = analyzer.process(&mut buffer).to_vec();
+        let mut different_data = (0..1024).map(|i| (i as f32 * 0.1).sin()).collect::<Vec<_>>();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut different_data]
+            });
+        }
+
+        // Act
+        analyzer.set_frozen(true);
+        let frozen = analyzer.process(&mut buffer).to_vec();
+        analyzer.set_frozen(false);
+        let unfrozen = analyzer.process(&mut buffer).to_vec();
+
+        // Assert
+        assert_eq!(frozen, before);
+        assert_ne!(unfrozen, before);
+    }
+
+    #[test]
+    fn reset_clears_the_smoothing_history() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_smoothing(0.5);
+        let mut channel1_data = vec![1.0; 1024];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+        analyzer.process(&mut buffer);
+        let mut fresh_data = vec![0.25; 1024];
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut fresh_data]
+            });
+        }
+
+        // Act
+        analyzer.reset();
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        assert!((results[0].magnitudes[0] - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn smoothing_decay_frames_follows_the_smoothing() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+
+        // Act
+        let unsmoothed = analyzer.smoothing_decay_frames();
+        analyzer.set_smoothing(0.5);
+        let halved = analyzer.smoothing_decay_frames();
+        analyzer.set_smoothing(1.0);
+        let held = analyzer.smoothing_decay_frames();
+
+        // Assert
+        assert_eq!(unsmoothed, Some(0));
+        assert_eq!(halved, Some(10));
+        assert_eq!(held, None);
+    }
+
+    #[test]
+    fn the_offline_profile_enlarges_the_fft_until_it_is_turned_off() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_fft_size(1024);
+
+        // Act
+        analyzer.set_offline(true);
+        let offline_bins = analyzer.frequencies().len();
+        analyzer.set_fft_size(2048);
+        let offline_fft_size = analyzer.fft_size();
+        analyzer.apply_config(*analyzer.config()).unwrap();
+        let reapplied_fft_size = analyzer.fft_size();
+        let configured_fft_size = analyzer.config().fft_size;
+        analyzer.set_offline(false);
+
+        // Assert
+        assert_eq!(offline_bins, 2048);
+        assert_eq!(offline_fft_size, 8192);
+        assert_eq!(reapplied_fft_size, 8192);
+        assert_eq!(configured_fft_size, 2048);
+        assert_eq!(analyzer.fft_size(), 2048);
+        assert_eq!(analyzer.frequencies().len(), 1024);
+    }
+
+    #[test]
+    fn filter_state_repairs_values_saved_by_an_older_build() {
+        // Arrange
+        let mut state = PluginState {
+            version: String::from("0.0.1"),
+            params: BTreeMap::from([
+                (String::from("fft_size"), ParamValue::String(String::from("3000"))),
+                (String::from("window"), ParamValue::String(String::from("kaiser"))),
+                (String::from("smoothing"), ParamValue::F32(2.0)),
+                (String::from("slope"), ParamValue::F32(f32::NAN)),
+            ]),
+            fields: BTreeMap::new(),
+        };
+        let mut legacy_state = state.clone();
+        legacy_state.params.insert(String::from("fft_size"), ParamValue::I32(1000));
+        let blackman_harris = WindowFunction::BlackmanHarris.to_id() as i32;
+        legacy_state.params.insert(String::from("window"), ParamValue::I32(blackman_harris));
+
+        // Act
+        SpectrumAnalyzer::filter_state(&mut state);
+        SpectrumAnalyzer::filter_state(&mut legacy_state);
+
+        // Assert
+        assert_eq!(state.params["fft_size"], ParamValue::String(String::from("4096")));
+        assert_eq!(state.params["window"], ParamValue::String(String::from("hann")));
+        assert_eq!(state.params["smoothing"], ParamValue::F32(1.0));
+        assert_eq!(state.params["slope"], ParamValue::F32(0.0));
+        assert_eq!(legacy_state.params["fft_size"], ParamValue::String(String::from("1024")));
+        assert_eq!(
+            legacy_state.params["window"],
+            ParamValue::String(String::from("blackman-harris"))
+        );
+    }
+
+    #[test]
+    fn migrate_treats_a_state_without_a_version_as_the_oldest() {
+        // Arrange
+        let mut state = PluginState {
+            version: String::new(),
+            params: BTreeMap::from([(
+                String::from("fft"),
+                ParamValue::String(String::from("1024")),
+            )]),
+            fields: BTreeMap::new(),
+        };
+
+        // Act
+        migrate(&mut state);
+
+        // Assert
+        assert!(!state.params.contains_key("fft"));
+        assert_eq!(state.params["fft_size"], ParamValue::String(String::from("1024")));
+        assert_eq!(state.version, env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn migrate_leaves_a_state_from_a_newer_build_untouched() {
+        // Arrange
+        let mut state = PluginState {
+            version: String::from("99.0.0"),
+            params: BTreeMap::from([(
+                String::from("fft"),
+                ParamValue::String(String::from("1024")),
+            )]),
+            fields: BTreeMap::new(),
+        };
+        let saved = state.clone();
+
+        // Act
+        migrate(&mut state);
+
+        // Assert
+        assert_eq!(state.version, saved.version);
+        assert_eq!(state.params, saved.params);
+    }
+
+    #[test]
+    fn reference_curve_round_trips_through_the_plugin_state() {
+        // Arrange
+        let curve = ReferenceCurve {
+            fft_size: 1024,
+            channels: vec![vec![0.5, 0.25, 1e-7], vec![1.0, 0.0, 0.125]],
+        };
+        let params = SpectrumAnalyzerParams::default();
+        *params.reference_curve.write().unwrap() = curve.encode();
+        let restored_params = SpectrumAnalyzerParams::default();
+
+        // Act
+        let fields = params.serialize_fields();
+        restored_params.deserialize_fields(&fields);
+        let restored = ReferenceCurve::decode(&restored_params.reference_curve.read().unwrap());
+
+        // Assert
+        // The key of the `persist` attribute, which saved projects have the curve under.
+        assert!(fields.contains_key("reference_curve"));
+        assert_eq!(restored, Some(curve));
+    }
+
+    #[test]
+    fn capture_reference_task_stores_the_reference_curve() {
+        // Arrange
+        let mut plugin = SpectrumAnalyzer::default();
+        let executor = plugin.task_executor();
+        let task = AnalyzerTask::CaptureReference { fft_size: 1024 };
+
+        // Act
+        std::thread::spawn(move || executor(task)).join().unwrap();
+        let curve = plugin.reference_curve();
+
+        // Assert
+        // Nothing was published yet, so there are no channels to capture.
+        assert_eq!(curve, Some(ReferenceCurve { fft_size: 1024, channels: Vec::new() }));
+    }
+
+    #[test]
+    fn reference_curve_is_resampled_to_another_fft_size() {
+        // Arrange
+        let curve = ReferenceCurve { fft_size: 4, channels: vec![vec![0.0, 1.0]] };
+
+        // Act
+        let resampled = curve.resampled(8);
+
+        // Assert
+        assert_eq!(resampled.fft_size, 8);
+        assert_eq!(resampled.channels, vec![vec![0.0, 0.5, 1.0, 1.0]]);
+    }
+
+    #[test]
+    fn filter_state_drops_unknown_parameters_and_fills_in_missing_ones() {
+        // Arrange
+        let mut state = PluginState {
+            version: String::from(env!("CARGO_PKG_VERSION")),
+            params: BTreeMap::from([
+                (String::from("fft_size"), ParamValue::String(String::from("1024"))),
+                (String::from("window"), ParamValue::String(String::from("blackman"))),
+                (String::from("smoothing"), ParamValue::F32(0.25)),
+                (String::from("freeze"), ParamValue::Bool(true)),
+                (String::from("peak_hold"), ParamValue::Bool(true)),
+            ]),
+            fields: BTreeMap::new(),
+        };
+
+        // Act
+        SpectrumAnalyzer::filter_state(&mut state);
+
+        // Assert
+        assert!(!state.params.contains_key("peak_hold"));
+        assert_eq!(state.params["slope"], ParamValue::F32(0.0));
+        assert_eq!(state.params["fft_size"], ParamValue::String(String::from("1024")));
+        assert_eq!(state.params["freeze"], ParamValue::Bool(true));
+    }
+
+    #[test]
+    fn process_returns_a_result_for_every_surround_channel() {
+        // Arrange
+        let mut analyzer = Analyzer::new(48000.0);
+        analyzer.prepare(6, 1024);
+        let mut channels =
+            (0..6).map(|channel| vec![channel as f32 / 6.0; 1024]).collect::<Vec<_>>();
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = channels.iter_mut().map(Vec::as_mut_slice).collect()
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        assert_eq!(results.len(), 6);
+        for (channel, result) in results.iter().enumerate() {
+            assert!((result.magnitudes[0] - channel as f32 / 6.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn results_channel_publishes_the_reference_after_the_main_results() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let mut reference_analyzer = Analyzer::new(44100.0);
+        let (mut input, mut output) = results_channel(2, 2, analyzer.max_bin_count(2));
+        let mut channel1_data = vec![1.0; 1024];
+        let mut channel2_data = vec![1.0; 1024];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data, &mut channel2_data]
+            });
+        }
+        let mut reference1_data = vec![0.5; 1024];
+        let mut reference2_data = vec![0.5; 1024];
+        let mut reference = Buffer::default();
+        unsafe {
+            reference.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut reference1_data, &mut reference2_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+        let reference_results = reference_analyzer.process(&mut reference);
+        input.publish(results, reference_results);
+        let published = output.read();
+
+        // Assert
+        assert_eq!(published.len(), 4);
+        assert_eq!(published[0].label, None);
+        assert_eq!(published[2].label.as_deref(), Some(REFERENCE_LABEL));
+        assert_eq!(published[3].label.as_deref(), Some(REFERENCE_LABEL));
+        assert!((published[0].magnitudes[0] - 1.0).abs() < 1e-3);
+        assert!((published[2].magnitudes[0] - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn prepare_resizes_the_channel_state_from_mono_to_stereo() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.prepare(1, 1024);
+        let mut channel1_data = vec![1.0; 1024];
+        let mut channel2_data = vec![0.5; 1024];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data, &mut channel2_data]
+            });
+        }
+
+        // Act
+        analyzer.prepare(2, 1024);
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        assert_eq!(results.len(), 2);
+        assert!((results[0].magnitudes[0] - 1.0).abs() < 1e-3);
+        assert!((results[1].magnitudes[0] - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn sidechain_layout_names_its_aux_input_reference() {
+        // Arrange
+        let layout = SpectrumAnalyzer::AUDIO_IO_LAYOUTS
+            .iter()
+            .find(|layout| !layout.aux_input_ports.is_empty())
+            .unwrap();
+
+        // Act
+        let name = layout.aux_input_name(0);
+
+        // Assert
+        assert_eq!(name.as_deref(), Some(REFERENCE_LABEL));
+        assert_eq!(layout.main_input_name(), "Analyzed Input");
+    }
+
+    #[test]
+    fn note_to_hz_follows_equal_temperament() {
+        // Arrange
+        let notes = [57, 69, 81];
+
+        // Act
+        let frequencies = notes.map(|note| note_to_hz(note, DEFAULT_REFERENCE_A4));
+
+        // Assert
+        assert!((frequencies[0] - 220.0).abs() < 1e-3);
+        assert!((frequencies[1] - 440.0).abs() < 1e-3);
+        assert!((frequencies[2] - 880.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn held_notes_mark_fundamentals_and_harmonics_until_released() {
+        // Arrange
+        let held_notes = HeldNotes::default();
+        held_notes.note_on(69);
+        held_notes.note_on(57);
+        held_notes.note_on(60);
+
+        // Act
+        held_notes.note_off(60);
+        let markers = held_notes.markers(DEFAULT_REFERENCE_A4, 2, 1000.0);
+
+        // Assert
+        assert_eq!(held_notes.notes(), vec![57, 69]);
+        let frequencies = markers.iter().map(|marker| marker.frequency).collect::<Vec<_>>();
+        assert_eq!(frequencies.len(), 5);
+        for (frequency, expected) in frequencies.iter().zip([220.0, 440.0, 660.0, 440.0, 880.0]) {
+            assert!((frequency - expected).abs() < 1e-2);
+        }
+        assert_eq!(markers[4].harmonic, 2);
+    }
+
+    #[test]
+    fn frequency_to_cc_maps_the_audible_range_logarithmically() {
+        // Arrange
+        let frequencies = [10.0, 20.0, 632.5, 20_000.0, 40_000.0];
+
+        // Act
+        let values = frequencies.map(frequency_to_cc);
+
+        // Assert
+        assert_eq!(values, [0, 0, 64, 127, 127]);
+    }
+
+    #[test]
+    fn recompute_cqt_kernel_task_hands_the_kernel_to_the_plugin() {
+        // Arrange
+        let mut plugin = SpectrumAnalyzer::default();
+        let executor = plugin.task_executor();
+        let task = AnalyzerTask::RecomputeCqtKernel {
+            sample_rate: 44100.0,
+            bins_per_octave: 12,
+            f_min: 110.0,
+        };
+
+        let mut channel1_data = sine(440.0, 44100.0, 1024);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+        let mut analyzer = Analyzer::new(44100.0);
+
+        // Act
+        std::thread::spawn(move || executor(task)).join().unwrap();
+        let kernel = plugin.update_cqt_kernel();
+        analyzer.process_cqt(&mut buffer, 12, 110.0);
+
+        // Assert
+        assert!(kernel.is_some_and(|kernel| kernel.matches(44100.0, 12, 110.0)));
+        assert!(kernel == analyzer.cqt_kernel());
+    }
+
+    #[test]
+    fn cqt_kernel_is_only_computed_while_the_cqt_is_shown() {
+        // Arrange
+        let mut plugin = SpectrumAnalyzer::default();
+        let executor = plugin.task_executor();
+        let mut context = TestContext::default();
+        let buffer_config = BufferConfig {
+            sample_rate: 48000.0,
+            min_buffer_size: None,
+            max_buffer_size: 512,
+            process_mode: ProcessMode::Realtime,
+        };
+        plugin.initialize(&SpectrumAnalyzer::AUDIO_IO_LAYOUTS[1], &buffer_config, &mut context);
+        let mut channel1_data = sine(440.0, 48000.0, 512);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(512, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+        let mut aux = AuxiliaryBuffers { inputs: &mut [], outputs: &mut [] };
+
+        // Act
+        plugin.process(&mut buffer, &mut aux, &mut context);
+        let tasks_while_hidden = context.tasks.take();
+        plugin.set_cqt_enabled(true);
+        plugin.process(&mut buffer, &mut aux, &mut context);
+        plugin.process(&mut buffer, &mut aux, &mut context);
+        let tasks = context.tasks.take();
+        tasks.iter().copied().for_each(&executor);
+        plugin.process(&mut buffer, &mut aux, &mut context);
+
+        // Assert
+        assert!(tasks_while_hidden.is_empty());
+        assert_eq!(tasks.len(), 1);
+        assert!(context.tasks.borrow().is_empty());
+        let AnalyzerTask::RecomputeCqtKernel { sample_rate, bins_per_octave, f_min } = tasks[0]
+        else {
+            panic!("Expected a RecomputeCqtKernel task, got {:?}", tasks[0]);
+        };
+        assert_eq!(sample_rate, 48000.0);
+        let mut analyzer = Analyzer::new(sample_rate);
+        analyzer.process_cqt(&mut buffer, bins_per_octave, f_min);
+        assert!(plugin.update_cqt_kernel() == analyzer.cqt_kernel());
+    }
+
+    #[test]
+    fn frequency_axis_gives_every_decade_the_same_width() {
+        // Arrange
+        let (left, width) = (10.0, 300.0);
+
+        // Act
+        let positions = [20.0, 200.0, 2000.0, 20_000.0].map(|f| frequency_to_x(f, left, width));
+
+        // Assert
+        assert_eq!(positions[0], 10.0);
+        assert!((positions[1] - 110.0).abs() < 1e-3);
+        assert!((positions[2] - 210.0).abs() < 1e-3);
+        assert!((positions[3] - 310.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn x_to_frequency_inverts_frequency_to_x() {
+        // Arrange
+        let (left, width) = (0.0, 640.0);
+
+        // Act
+        let frequencies = [100.0, 1000.0, 10_000.0]
+            .map(|frequency| x_to_frequency(frequency_to_x(frequency, left, width), left, width));
+
+        // Assert
+        for (frequency, expected) in frequencies.iter().zip([100.0, 1000.0, 10_000.0]) {
+            assert!((frequency - expected).abs() / expected < 1e-4);
+        }
+    }
+
+    #[test]
+    fn magnitude_at_interpolates_between_the_surrounding_bins() {
+        // Arrange
+        let result = AnalyzerResult {
+            frequencies: vec![0.0, 100.0, 200.0],
+            magnitudes: vec![0.0, 1.0, 0.5],
+            label: None,
+            channel: 0,
+            phases: None,
+            start_bin: 0,
+            rms: 0.0,
+            peak: 0.0,
+        };
+
+        // Act
+        let magnitudes = [0.0, 50.0, 100.0, 150.0, 200.0].map(|f| result.magnitude_at(f));
+
+        // Assert
+        assert_eq!(magnitudes, [0.0, 0.5, 1.0, 0.75, 0.5]);
+    }
+
+    #[test]
+    fn magnitude_at_clamps_to_the_outermost_bins() {
+        // Arrange
+        let result = AnalyzerResult {
+            frequencies: vec![100.0, 200.0],
+            magnitudes: vec![0.25, 0.5],
+            label: None,
+            channel: 0,
+            phases: None,
+            start_bin: 0,
+            rms: 0.0,
+            peak: 0.0,
+        };
+
+        // Act
+        let below = result.magnitude_at(50.0);
+        let above = result.magnitude_at(1000.0);
+
+        // Assert
+        assert_eq!(below, 0.25);
+        assert_eq!(above, 0.5);
+    }
+
+    #[test]
+    fn diff_subtracts_the_magnitudes_of_every_bin() {
+        // Arrange
+        let result = AnalyzerResult {
+            frequencies: vec![0.0, 100.0, 200.0],
+            magnitudes: vec![0.25, 1.0, 0.5],
+            ..AnalyzerResult::default()
+        };
+        let scaled = AnalyzerResult {
+            magnitudes: result.magnitudes.iter().map(|magnitude| magnitude * 2.0).collect(),
+            ..result.clone()
+        };
+        let shorter = AnalyzerResult { magnitudes: vec![0.25], ..result.clone() };
+
+        // Act
+        let to_itself = result.diff(&result);
+        let to_scaled = scaled.diff(&result);
+        let to_shorter = result.diff(&shorter);
+
+        // Assert
+        assert_eq!(to_itself, [0.0, 0.0, 0.0]);
+        assert_eq!(to_scaled, [0.25, 1.0, 0.5]);
+        assert!(to_shorter.is_empty());
+    }
+
+    #[test]
+    fn silent_block_decays_the_previous_results_without_analyzing() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_smoothing(0.5);
+        analyzer.set_silence_threshold(-120.0);
+        let mut channel1_data = vec![1.0; 1024];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+        analyzer.process(&mut buffer);
+        let loud_blocks = analyzer.silent_blocks();
+        let mut silence = vec![0.0; 1024];
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut silence]
+            });
+        }
+
+        // Act
+        analyzer.process(&mut buffer);
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        assert_eq!(loud_blocks, 0);
+        assert!((results[0].magnitudes[0] - 0.25).abs() < 1e-3);
+        assert_eq!(analyzer.silent_blocks(), 2);
+    }
+
+    #[test]
+    fn process_complex_returns_a_conjugate_symmetric_full_spectrum() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let mut channel1_data = (0..1024)
+            .map(|i| (i as f32 * 0.3).sin() + 0.5 * (i as f32 * 0.05).cos())
+            .collect::<Vec<_>>();
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let spectra = analyzer.process_complex(&mut buffer);
+
+        // Assert
+        assert_eq!(spectra.len(), 1);
+        let spectrum = &spectra[0];
+        assert_eq!(spectrum.len(), analyzer.fft_size());
+        for k in 1..spectrum.len() / 2 {
+            let mirrored = spectrum[spectrum.len() - k].conj();
+            assert!((spectrum[k] - mirrored).norm() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn phase_correlation_of_identical_channels_is_one() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let mut channel1_data = (0..1024).map(|i| (i as f32 * 0.1).sin()).collect::<Vec<_>>();
+        let mut channel2_data = channel1_data.clone();
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data, &mut channel2_data]
+            });
+        }
+
+        // Act
+        let correlation = analyzer.phase_correlation(&mut buffer);
+
+        // Assert
+        assert!((correlation.unwrap() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn phase_correlation_of_inverted_channels_is_minus_one() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let mut channel1_data = (0..1024).map(|i| (i as f32 * 0.1).sin()).collect::<Vec<_>>();
+        let mut channel2_data = channel1_data.iter().map(|sample| -sample).collect::<Vec<_>>();
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data, &mut channel2_data]
+            });
+        }
+
+        // Act
+        let correlation = analyzer.phase_correlation(&mut buffer);
+
+        // Assert
+        assert!((correlation.unwrap() + 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn phase_correlation_of_uncorrelated_noise_is_near_zero() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let mut channel1_data = white_noise(0x1234_5678, 8192);
+        let mut channel2_data = white_noise(0x8765_4321, 8192);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(8192, |output_slices| {
+                *output_slices = vec![&mut channel1_data, &mut channel2_data]
+            });
+        }
+
+        // Act
+        let correlation = analyzer.phase_correlation(&mut buffer);
+
+        // Assert
+        assert!(correlation.unwrap().abs() < 0.05, "correlation was {correlation:?}");
+    }
+
+    #[test]
+    fn phase_correlation_of_a_mono_buffer_is_none() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let mut channel1_data = vec![0.5; 1024];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let correlation = analyzer.phase_correlation(&mut buffer);
+
+        // Assert
+        assert_eq!(correlation, None);
+    }
+
+    #[test]
+    fn transfer_function_of_an_attenuated_copy_is_flat_and_coherent() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let noise = white_noise(0x1234_5678, 8 * 2048);
+        let mut result = TransferResult::default();
+
+        // Act
+        for block in noise.chunks(2048) {
+            let mut reference_data = block.to_vec();
+            let mut main_data =
+                reference_data.iter().map(|sample| -0.5 * sample).collect::<Vec<_>>();
+            let mut main = Buffer::default();
+            let mut reference = Buffer::default();
+            unsafe {
+                main.set_slices(2048, |output_slices| *output_slices = vec![&mut main_data]);
+                reference.set_slices(2048, |output_slices| {
+                    *output_slices = vec![&mut reference_data]
+                });
+            }
+            result = analyzer.transfer_function(&mut main, &mut reference);
+        }
+
+        // Assert
+        let bin_count = analyzer.fft_size() / 2 + 1;
+        assert_eq!(result.frequencies.len(), bin_count);
+        for bin in 1..bin_count - 1 {
+            assert!((result.magnitude_db[bin] + 6.0206).abs() < 1e-2);
+            assert!((result.phase_deg[bin].abs() - 180.0).abs() < 1e-2);
+            assert!(result.coherence[bin] > 0.999);
+        }
+    }
+
+    #[test]
+    fn goertzel_detects_a_tone_at_its_frequency() {
+        // Arrange
+        let analyzer = Analyzer::new(48000.0);
+        let samples = sine(1000.0, 48000.0, 4800);
+
+        // Act
+        let magnitude = analyzer.goertzel(&samples, 1000.0);
+
+        // Assert
+        assert!((magnitude - 1.0).abs() < 1e-2, "magnitude was {magnitude}");
+    }
+
+    #[test]
+    fn goertzel_barely_responds_away_from_the_tone() {
+        // Arrange
+        let analyzer = Analyzer::new(48000.0);
+        let samples = sine(1000.0, 48000.0, 4800);
+
+        // Act
+        let magnitude = analyzer.goertzel(&samples, 5000.0);
+
+        // Assert
+        assert!(magnitude < 1e-2, "magnitude was {magnitude}");
+    }
+
+    #[test]
+    fn prime_length_blocks_are_analyzed_at_the_power_of_two_fft_size() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let fft_size = analyzer.fft_size();
+        let mut channel1_data = vec![0.5; 997];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(997, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+        let mut bin_counts = Vec::new();
+
+        // Act
+        for _ in 0..4 {
+            bin_counts.push(analyzer.process(&mut buffer)[0].magnitudes.len());
+        }
+
+        // Assert
+        assert!(fft_size.is_power_of_two());
+        assert_eq!(analyzer.fft_size(), fft_size);
+        assert!(bin_counts.iter().all(|&bin_count| bin_count == fft_size / 2));
+    }
+
+    #[test]
+    fn full_scale_sine_reports_its_rms_and_peak() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        let mut channel1_data = sine(1000.0, 44100.0, 4096);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(4096, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        assert!((results[0].peak - 1.0).abs() < 1e-3);
+        assert!((results[0].rms - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-2);
+    }
+
+    #[test]
+    fn frequency_range_excludes_the_bins_outside_of_it() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+        analyzer.set_frequency_range(20.0, 500.0).unwrap();
+        let fft_size = analyzer.fft_size();
+        let bin_width = 44100.0 / fft_size as f32;
+        let mut channel1_data = vec![0.5; 1024];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        let result = &results[0];
+        let expected_bins = (0..fft_size / 2)
+            .filter(|&bin| (20.0..=500.0).contains(&(bin as f32 * bin_width)))
+            .collect::<Vec<_>>();
+        assert_eq!(result.start_bin, expected_bins[0]);
+        assert_eq!(result.frequencies.len(), expected_bins.len());
+        assert_eq!(result.magnitudes.len(), expected_bins.len());
+        assert_eq!(result.frequencies[0], result.start_bin as f32 * bin_width);
+        assert!(result.frequencies.iter().all(|frequency| (20.0..=500.0).contains(frequency)));
+    }
+
+    #[test]
+    fn frequency_range_must_be_within_the_spectrum() {
+        // Arrange
+        let mut analyzer = Analyzer::new(44100.0);
+
+        // Act
+        let reversed = analyzer.set_frequency_range(500.0, 20.0);
+        let negative = analyzer.set_frequency_range(-1.0, 500.0);
+        let above_nyquist = analyzer.set_frequency_range(20.0, 30_000.0);
+
+        // Assert
+        assert_eq!(reversed, Err(ConfigError::InvalidFrequencyRange(500.0, 20.0)));
+        assert_eq!(negative, Err(ConfigError::InvalidFrequencyRange(-1.0, 500.0)));
+        assert_eq!(above_nyquist, Err(ConfigError::InvalidFrequencyRange(20.0, 30_000.0)));
+        assert_eq!(analyzer.frequency_range(), None);
+    }
+
+    #[test]
+    fn builder_applies_the_whole_configuration() {
+        // Arrange
+        let builder = Analyzer::builder()
+            .fft_size(2048)
+            .window(WindowFunction::Hann)
+            .overlap(0.75)
+            .smoothing(0.5)
+            .weighting(WeightingCurve::A);
+
+        // Act
+        let analyzer = builder.build(48000.0).unwrap();
+
+        // Assert
+        assert_eq!(analyzer.sample_rate(), 48000.0);
+        assert_eq!(analyzer.fft_size(), 2048);
+        assert_eq!(analyzer.window(), WindowFunction::Hann);
+        assert_eq!(analyzer.overlap(), 0.75);
+        assert_eq!(analyzer.smoothing(), 0.5);
+        assert_eq!(analyzer.weighting(), WeightingCurve::A);
+    }
+
+    #[test]
+    fn builder_rejects_invalid_combinations() {
+        // Arrange
+        let builder = AnalyzerBuilder::default();
+
+        // Act
+        let odd_size = builder.fft_size(1000).build(44100.0);
+        let full_overlap = builder.overlap(1.0).build(44100.0);
+        let no_sample_rate = builder.build(0.0);
+
+        // Assert
+        assert_eq!(odd_size.err(), Some(ConfigError::FftSizeNotPowerOfTwo(1000)));
+        assert_eq!(full_overlap.err(), Some(ConfigError::InvalidOverlap(1.0)));
+        assert_eq!(no_sample_rate.err(), Some(ConfigError::InvalidSampleRate(0.0)));
+    }
+
+    #[test]
+    fn prefilter_attenuates_energy_below_its_cutoff() {
+        // Arrange
+        let mut filtered = Analyzer::new(44100.0);
+        filtered.set_window(WindowFunction::Hann);
+        filtered.set_fft_size(4096);
+        filtered.set_prefilter_cutoff(500.0);
+        let mut plain = Analyzer::new(44100.0);
+        plain.set_window(WindowFunction::Hann);
+        plain.set_fft_size(4096);
+        let mut channel1_data = sine(50.0, 44100.0, 8192)
+            .into_iter()
+            .zip(sine(5000.0, 44100.0, 8192))
+            .map(|(low, high)| low + high)
+            .collect::<Vec<_>>();
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(8192, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let filtered_result = filtered.process(&mut buffer)[0].clone();
+        let plain_result = plain.process(&mut buffer)[0].clone();
+
+        // Assert
+        let ratio = |frequency| {
+            filtered_result.magnitude_at(frequency) / plain_result.magnitude_at(frequency)
+        };
+        assert!(ratio(50.0) < 0.2, "50 Hz was attenuated to {}", ratio(50.0));
+        assert!(ratio(5000.0) > 0.9, "5 kHz was attenuated to {}", ratio(5000.0));
+    }
+
+    #[test]
+    fn test_signals_are_deterministic() {
+        // Act
+        let white = (white_noise(7, 4096), white_noise(7, 4096), white_noise(8, 4096));
+        let pink = (pink_noise(7, 4096), pink_noise(7, 4096));
+        let impulse = impulse(8, 3);
+
+        // Assert
+        assert_eq!(white.0, white.1);
+        assert_ne!(white.0, white.2);
+        assert!(white.0.iter().all(|sample| (-1.0..1.0).contains(sample)));
+        assert_eq!(pink.0, pink.1);
+        assert!(pink.0.iter().all(|sample| sample.abs() <= 1.5));
+        assert_eq!(impulse, [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn magnitudes_match_a_complex_fft_of_the_same_frame() {
+        // Arrange
+        let mut analyzer = Analyzer::new(48000.0);
+        analyzer.set_window(WindowFunction::Hann);
+        let fft_size = analyzer.fft_size();
+        let mut channel1_data = white_noise(7, fft_size);
+        let mut window = Vec::new();
+        WindowFunction::Hann.fill(&mut window, fft_size);
+        let scale = 1.0 / (fft_size as f32 * WindowFunction::Hann.coherent_gain(fft_size));
+        let mut spectrum = channel1_data
+            .iter()
+            .zip(&window)
+            .map(|(sample, coefficient)| Complex::new(sample * coefficient, 0.0))
+            .collect::<Vec<_>>();
+        FftPlanner::new().plan_fft_forward(fft_size).process(&mut spectrum);
+        let expected = spectrum[..fft_size / 2]
+            .iter()
+            .enumerate()
+            .map(|(bin, value)| value.norm() * scale * if bin == 0 { 1.0 } else { 2.0 })
+            .collect::<Vec<_>>();
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(fft_size, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let results = analyzer.process(&mut buffer);
+
+        // Assert
+        assert_eq!(results[0].magnitudes.len(), expected.len());
+        for (magnitude, expected) in results[0].magnitudes.iter().zip(&expected) {
+            assert!((magnitude - expected).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn power_magnitudes_are_the_squares_of_linear_magnitudes() {
+        // Arrange
+        let mut linear = Analyzer::new(48000.0);
+        let mut power = Analyzer::new(48000.0);
+        power.set_magnitude_scale(MagnitudeScale::Power);
+        assert_eq!(power.magnitude_scale(), MagnitudeScale::Power);
+        let fft_size = linear.fft_size();
+        let mut channel1_data = pink_noise(3, fft_size);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(fft_size, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let expected = linear.process(&mut buffer)[0].magnitudes.clone();
+        let results = power.process(&mut buffer);
+
+        // Assert
+        assert_eq!(results[0].magnitudes.len(), expected.len());
+        for (power, linear) in results[0].magnitudes.iter().zip(&expected) {
+            assert!((power - linear * linear).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn process_into_reuses_the_callers_results() {
+        // Arrange
+        let mut analyzer = Analyzer::new(48000.0);
+        let fft_size = analyzer.fft_size();
+        let mut channel1_data = sine(1000.0, 48000.0, fft_size);
+        let mut channel2_data = white_noise(5, fft_size);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(fft_size, |output_slices| {
+                *output_slices = vec![&mut channel1_data, &mut channel2_data]
+            });
+        }
+        let mut out = vec![AnalyzerResult::default(); 3];
+
+        // Act
+        let first_count = analyzer.process_into(&mut buffer, &mut out);
+        let capacities = out
+            .iter()
+            .map(|result| (result.frequencies.capacity(), result.magnitudes.capacity()))
+            .collect::<Vec<_>>();
+        let second_count = analyzer.process_into(&mut buffer, &mut out);
+        let expected = analyzer.process(&mut buffer).to_vec();
+
+        // Assert
+        assert_eq!(first_count, 2);
+        assert_eq!(second_count, 2);
+        assert_eq!(&out[..2], &expected[..]);
+        assert!(out[2].magnitudes.is_empty());
+        for (result, &(frequencies, magnitudes)) in out.iter().zip(&capacities) {
+            assert_eq!(result.frequencies.capacity(), frequencies);
+            assert_eq!(result.magnitudes.capacity(), magnitudes);
+        }
+    }
+
+    #[test]
+    fn silence_is_reported_at_the_floor_in_db() {
+        // Arrange
+        let mut analyzer = Analyzer::new(48000.0);
+        let fft_size = analyzer.fft_size();
+        let mut channel1_data = vec![0.0; fft_size];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(fft_size, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let default_levels = analyzer.process_db(&mut buffer);
+        analyzer.set_floor_db(-80.0);
+        let raised_levels = analyzer.process_db(&mut buffer);
+
+        // Assert
+        assert_eq!(default_levels[0].len(), fft_size / 2);
+        assert!(default_levels[0].iter().all(|&level| level == DEFAULT_FLOOR_DB));
+        assert!(raised_levels[0].iter().all(|&level| level == -80.0));
+    }
+
+    #[test]
+    fn both_magnitude_scales_give_the_same_levels_in_db() {
+        // Arrange
+        let mut linear = Analyzer::new(48000.0);
+        let mut power = Analyzer::new(48000.0);
+        power.set_magnitude_scale(MagnitudeScale::Power);
+        let fft_size = linear.fft_size();
+        let mut channel1_data = pink_noise(3, fft_size);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(fft_size, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        // Act
+        let linear_levels = linear.process_db(&mut buffer);
+        let power_levels = power.process_db(&mut buffer);
+
+        // Assert
+        assert_eq!(power_levels[0].len(), linear_levels[0].len());
+        for (power, linear) in power_levels[0].iter().zip(&linear_levels[0]) {
+            assert!((power - linear).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn smoothing_time_is_converted_for_the_sample_rate() {
+        // Arrange
+        let mut analyzer = Analyzer::new(48000.0);
+        analyzer.set_smoothing_time(0.1);
+        let coefficient = analyzer.smoothing();
+
+        // Act
+        analyzer.set_sample_rate(96000.0);
+        let doubled_rate_coefficient = analyzer.smoothing();
+        analyzer.set_smoothing(0.5);
+
+        // Assert
+        // Twice as many frames per second must each keep the square root as much.
+        let expected = (-(1024.0 / 48000.0) / 0.1_f32).exp();
+        assert!((coefficient - expected).abs() < 1e-6);
+        assert!((doubled_rate_coefficient - coefficient.sqrt()).abs() < 1e-6);
+        assert_eq!(analyzer.smoothing_time(), None);
+        assert_eq!(analyzer.smoothing(), 0.5);
+    }
+
+    #[test]
+    fn smoothing_time_follows_the_block_length_of_process() {
+        // Arrange
+        let mut analyzer = Analyzer::new(48000.0);
+        analyzer.set_smoothing_time(0.1);
+        let mut data = sine(1000.0, 48000.0, 480);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(480, |output_slices| *output_slices = vec![&mut data]);
+        }
+
+        // Act
+        analyzer.process(&mut buffer);
+        let coefficient = analyzer.smoothing();
+
+        // Assert
+        // Every 10 ms block is a frame, however many samples a frame of poll() would hop.
+        let expected = (-(480.0 / 48000.0) / 0.1_f32).exp();
+        assert!((coefficient - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn settings_are_restored_when_the_guard_is_dropped() {
+        // Arrange
+        let mut analyzer = Analyzer::new(48000.0);
+        analyzer.set_smoothing(0.3);
+        let original = analyzer.settings();
+        let mut temporary = original.clone();
+        temporary.config.fft_size = 8192;
+        temporary.overlap = 0.75;
+        temporary.smoothing_time = Some(0.2);
+        temporary.config.window = WindowFunction::Hann;
+
+        // Act
+        let applied = {
+            let guard = analyzer.with_settings(&temporary).unwrap();
+            (guard.fft_size(), guard.overlap(), guard.smoothing_time())
+        };
+
+        // Assert
+        assert_eq!(applied, (8192, 0.75, Some(0.2)));
+        assert_eq!(analyzer.settings(), original);
+        assert_eq!(analyzer.fft_size(), 1024);
+        assert_eq!(analyzer.smoothing(), 0.3);
+    }
+
+    #[test]
+    fn invalid_temporary_settings_are_not_applied() {
+        // Arrange
+        let mut analyzer = Analyzer::new(48000.0);
+        let original = analyzer.settings();
+        let temporary = AnalyzerSettings {
+            frequency_range: Some((100.0, 30000.0)),
+            overlap: 0.5,
+            ..original.clone()
+        };
+
+        // Act
+        let result = analyzer.with_settings(&temporary).map(|_| ());
+
+        // Assert
+        assert_eq!(result, Err(ConfigError::InvalidFrequencyRange(100.0, 30000.0)));
+        assert_eq!(analyzer.settings(), original);
+    }
+
+    #[test]
+    fn samples_that_overflow_the_queue_are_counted_as_dropped_frames() {
+        // Arrange
+        let mut analyzer = Analyzer::new(48000.0);
+        analyzer.set_fft_size(256);
+        analyzer.set_pending_capacity(Some(1024));
+        let samples = white_noise(11, 256 * 100);
+
+        // Act
+        analyzer.push_samples(0, &samples);
+        analyzer.push_samples(1, &samples);
+        let queued = std::iter::from_fn(|| analyzer.poll()).count();
+        let counts = (analyzer.frames_processed(), analyzer.frames_dropped());
+        analyzer.reset();
+
+        // Assert
+        // Both channels keep the last 1024 samples, four frames of 256 samples, and lose the 96
+        // frames before them.
+        assert_eq!(queued, 4);
+        assert_eq!(counts, (4, 96));
+        assert_eq!(analyzer.frames_processed(), 0);
+        assert_eq!(analyzer.frames_dropped(), 0);
+    }
+
+    #[test]
+    fn switching_between_prepared_fft_sizes_does_not_allocate() {
+        // Arrange
+        let mut analyzer = Analyzer::new(48000.0);
+        analyzer.prepare(2, 512);
+        analyzer.prepare_fft_sizes(2, &FftSize::ALL.map(FftSize::samples));
+        let (mut results_input, _results_output) =
+            results_channel(2, 0, analyzer.max_bin_count(2));
+        let mut channel1_data = sine(1000.0, 48000.0, 512);
+        let mut channel2_data = white_noise(13, 512);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(512, |output_slices| {
+                *output_slices = vec![&mut channel1_data, &mut channel2_data]
+            });
+        }
+        analyzer.process(&mut buffer);
+
+        // Act
+        let bin_counts = assert_no_alloc(|| {
+            let mut bin_counts = [0; 3];
+            let fft_sizes = [FftSize::Size8192, FftSize::Size512, FftSize::Size4096];
+            for (bin_count, fft_size) in bin_counts.iter_mut().zip(fft_sizes) {
+                analyzer.set_fft_size(fft_size.samples());
+                analyzer.set_window(WindowFunction::Blackman);
+                let results = analyzer.process(&mut buffer);
+                results_input.publish(results, &[]);
+                *bin_count = results[0].magnitudes.len();
+            }
+            bin_counts
+        });
+
+        // Assert
+        assert_eq!(bin_counts, [4096, 256, 2048]);
+    }
+
+    #[test]
+    fn analyzer_adds_no_latency_with_large_frames_overlap_or_the_offline_profile() {
+        // Arrange
+        let configurations = [(8192, 0.0, false), (2048, 0.75, false), (8192, 0.75, true)];
+        let mut channel1_data = sine(1000.0, 44100.0, 512);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(512, |output_slices| {
+                *output_slices = vec![&mut channel1_data]
+            });
+        }
+
+        for (fft_size, overlap, offline) in configurations {
+            let mut analyzer = Analyzer::new(44100.0);
+            analyzer.set_fft_size(fft_size);
+            analyzer.set_overlap(overlap);
+            analyzer.set_offline(offline);
+
+            // Act
+            analyzer.process(&mut buffer);
+
+            // Assert
+            assert_eq!(analyzer.latency_samples(), 0, "{fft_size} samples, {overlap} overlap");
+        }
+    }
+
+    #[test]
+    fn plugin_reports_no_latency_when_rendering_offline() {
+        // Arrange
+        let mut plugin = SpectrumAnalyzer::default();
+        let mut context = TestContext::default();
+        let buffer_config = BufferConfig {
+            sample_rate: 96000.0,
+            min_buffer_size: None,
+            max_buffer_size: 4096,
+            process_mode: ProcessMode::Offline,
+        };
+
+        // Act
+        plugin.initialize(&SpectrumAnalyzer::AUDIO_IO_LAYOUTS[0], &buffer_config, &mut context);
+
+        // Assert
+        assert_eq!(context.latency_samples.get(), Some(0));
+    }
+
+    #[test]
+    fn process_status_asks_for_a_tail_while_the_smoothing_decays() {
+        // Arrange
+        let mut tone_data = sine(1000.0, 44100.0, 512);
+        let mut tone = Buffer::default();
+        unsafe {
+            tone.set_slices(512, |output_slices| {
+                *output_slices = vec![&mut tone_data]
+            });
+        }
+        let mut silence_data = vec![0.0; 512];
+        let mut silence = Buffer::default();
+        unsafe {
+            silence.set_slices(512, |output_slices| {
+                *output_slices = vec![&mut silence_data]
+            });
+        }
+        let analyzer_with_smoothing = |smoothing: f32| {
+            let mut analyzer = Analyzer::new(44100.0);
+            analyzer.set_fft_size(2048);
+            analyzer.set_overlap(0.5);
+            analyzer.set_smoothing(smoothing);
+            analyzer.set_silence_threshold(-120.0);
+            analyzer
+        };
+        let mut unsmoothed = analyzer_with_smoothing(0.0);
+        let mut decaying = analyzer_with_smoothing(0.5);
+        let mut held = analyzer_with_smoothing(1.0);
+        let mut settled = analyzer_with_smoothing(0.5);
+
+        // Act
+        for analyzer in [&mut unsmoothed, &mut decaying, &mut held, &mut settled] {
+            analyzer.process(&mut tone);
+        }
+        for _ in 0..10 {
+            settled.process(&mut silence);
+        }
+
+        // Assert
+        // Halving every frame takes 10 frames to decay by 60 dB, and a frame starts every 1024
+        // samples.
+        assert_eq!(process_status(&unsmoothed), ProcessStatus::Normal);
+        assert_eq!(process_status(&decaying), ProcessStatus::Tail(10 * 1024));
+        assert_eq!(process_status(&held), ProcessStatus::KeepAlive);
+        assert_eq!(process_status(&settled), ProcessStatus::Normal);
+    }
+
+    #[test]
+    fn plugin_tail_does_not_depend_on_the_block_size() {
+        // Arrange
+        let mut plugin = SpectrumAnalyzer::default();
+        let mut context = TestContext::default();
+        let buffer_config = BufferConfig {
+            sample_rate: 48000.0,
+            min_buffer_size: None,
+            max_buffer_size: 1024,
+            process_mode: ProcessMode::Realtime,
+        };
+        plugin.initialize(&SpectrumAnalyzer::AUDIO_IO_LAYOUTS[1], &buffer_config, &mut context);
+        let mut short_data = sine(1000.0, 48000.0, 256);
+        let mut short = Buffer::default();
+        unsafe {
+            short.set_slices(256, |output_slices| {
+                *output_slices = vec![&mut short_data]
+            });
+        }
+        let mut long_data = sine(1000.0, 48000.0, 1024);
+        let mut long = Buffer::default();
+        unsafe {
+            long.set_slices(1024, |output_slices| {
+                *output_slices = vec![&mut long_data]
+            });
+        }
+        let mut aux = AuxiliaryBuffers { inputs: &mut [], outputs: &mut [] };
+
+        // Act
+        let short_status = plugin.process(&mut short, &mut aux, &mut context);
+        let long_status = plugin.process(&mut long, &mut aux, &mut context);
+
+        // Assert
+        // The default smoothing of 0.5 decays in 10 frames of the default FFT size of 2048
+        // samples, which don't overlap.
+        assert_eq!(short_status, ProcessStatus::Tail(10 * 2048));
+        assert_eq!(long_status, short_status);
+    }
+
+    /// A host for [`SpectrumAnalyzer::initialize()`] and [`SpectrumAnalyzer::process()`] that
+    /// records the tasks the plugin starts and the latency it reports, instead of acting on them.
+    #[derive(Default)]
+    struct TestContext {
+        tasks: RefCell<Vec<AnalyzerTask>>,
+        latency_samples: Cell<Option<u32>>,
+    }
+
+    impl InitContext<SpectrumAnalyzer> for TestContext {
+        fn plugin_api(&self) -> PluginApi {
+            PluginApi::Clap
+        }
+
+        fn execute(&self, task: AnalyzerTask) {
+            self.tasks.borrow_mut().push(task);
+        }
+
+        fn set_latency_samples(&self, samples: u32) {
+            self.latency_samples.set(Some(samples));
+        }
+
+        fn set_current_voice_capacity(&self, _capacity: u32) {}
+    }
+
+    impl ProcessContext<SpectrumAnalyzer> for TestContext {
+        fn plugin_api(&self) -> PluginApi {
+            PluginApi::Clap
+        }
+
+        fn execute_background(&self, task: AnalyzerTask) {
+            self.tasks.borrow_mut().push(task);
+        }
+
+        fn execute_gui(&self, task: AnalyzerTask) {
+            self.tasks.borrow_mut().push(task);
+        }
+
+        fn transport(&self) -> &Transport {
+            unimplemented!("The plugin doesn't read the transport")
+        }
+
+        fn next_event(&mut self) -> Option<PluginNoteEvent<SpectrumAnalyzer>> {
+            None
+        }
+
+        fn send_event(&mut self, _event: PluginNoteEvent<SpectrumAnalyzer>) {}
+
+        fn set_latency_samples(&self, samples: u32) {
+            self.latency_samples.set(Some(samples));
+        }
+
+        fn set_current_voice_capacity(&self, _capacity: u32) {}
     }
-}
\ No newline at end of file
+}